@@ -0,0 +1,44 @@
+//! Off-chain helpers for reading counter state.
+
+use alloc::{ string::String, vec::Vec };
+use base64::{ Engine, engine::general_purpose::STANDARD };
+use borsh::BorshDeserialize;
+use solana_program::{ program_error::ProgramError, pubkey::Pubkey };
+
+use crate::{ CounterAccount, event::CounterEvent };
+
+/// Decodes raw account data into a `CounterAccount`, checking that the account is
+/// owned by `program_id` and written with a supported layout.
+pub fn decode_counter(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    data: &[u8]
+) -> Result<CounterAccount, ProgramError> {
+    if owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    CounterAccount::unpack(data)
+}
+
+/// Fetches and decodes the counter at `counter` over RPC.
+#[cfg(feature = "client")]
+pub fn fetch_counter(
+    rpc_client: &solana_rpc_client::rpc_client::RpcClient,
+    program_id: &Pubkey,
+    counter: &Pubkey
+) -> Result<CounterAccount, alloc::boxed::Box<dyn core::error::Error>> {
+    let account = rpc_client.get_account(counter)?;
+    Ok(decode_counter(program_id, &account.owner, &account.data)?)
+}
+
+/// Decodes every `CounterEvent` found in a transaction's `Program data:` log lines.
+///
+/// Lines that don't decode as a counter event, e.g. data logged by other programs in
+/// the same transaction, are skipped.
+pub fn parse_counter_events(logs: &[String]) -> Vec<CounterEvent> {
+    logs.iter()
+        .filter_map(|log| log.strip_prefix("Program data: "))
+        .filter_map(|data| STANDARD.decode(data).ok())
+        .filter_map(|bytes| CounterEvent::try_from_slice(&bytes).ok())
+        .collect()
+}
@@ -0,0 +1,29 @@
+//! Helpers for other on-chain programs that drive a counter through CPI.
+//!
+//! Callers depending on this crate should enable its `no-entrypoint` feature.
+
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    program::invoke_signed,
+    pubkey::Pubkey,
+};
+
+use crate::instruction;
+
+/// Increments `counter` on behalf of `authority`.
+///
+/// `signer_seeds` holds the seeds when `authority` is a PDA of the calling program, and is
+/// empty when `authority` already signed the outer transaction.
+pub fn increment_counter_cpi<'a>(
+    program_id: &Pubkey,
+    counter: &AccountInfo<'a>,
+    authority: &AccountInfo<'a>,
+    signer_seeds: &[&[&[u8]]]
+) -> ProgramResult {
+    invoke_signed(
+        &instruction::increment_counter(program_id, counter.key, authority.key),
+        &[counter.clone(), authority.clone()],
+        signer_seeds
+    )
+}
@@ -0,0 +1,95 @@
+//! Structured events emitted with `sol_log_data` so indexers can follow counter changes.
+//!
+//! Each event is Borsh-serialized into a single data field and appears in the
+//! transaction logs as `Program data: <base64>`.
+
+use borsh::{ BorshDeserialize, BorshSerialize };
+use solana_program::{ entrypoint::ProgramResult, log::sol_log_data, pubkey::Pubkey };
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CounterOperation {
+    Increment,
+    Decrement,
+    Reset,
+    Set,
+    Add,
+    Subtract,
+    SetStep,
+    Pause,
+    Resume,
+    IncrementMany,
+    TransferAuthority,
+    Multiply,
+    CompareAndSet,
+    SetWrapping,
+    Clamp,
+    Freeze,
+    SetModulus,
+    RenounceAuthority,
+    Configure,
+    ProposeAuthority,
+    CancelAuthorityProposal,
+}
+
+impl CounterOperation {
+    /// Whether the operation may change the count, so it belongs in the counter's history.
+    /// Settings, pause and authority changes don't.
+    pub fn changes_count(self) -> bool {
+        matches!(
+            self,
+            Self::Increment |
+                Self::Decrement |
+                Self::Reset |
+                Self::Set |
+                Self::Add |
+                Self::Subtract |
+                Self::IncrementMany |
+                Self::Multiply |
+                Self::CompareAndSet |
+                Self::Clamp |
+                Self::Configure
+        )
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, PartialEq, Eq)]
+pub enum CounterEvent {
+    Initialized {
+        counter: Pubkey,
+        value: u64,
+    },
+    Updated {
+        counter: Pubkey,
+        operation: CounterOperation,
+        old_value: u64,
+        new_value: u64,
+    },
+    /// `Updated` for a `CounterAccountU128`.
+    UpdatedU128 {
+        counter: Pubkey,
+        operation: CounterOperation,
+        old_value: u128,
+        new_value: u128,
+    },
+    /// `Updated` for a `SignedCounterAccount`.
+    UpdatedSigned {
+        counter: Pubkey,
+        operation: CounterOperation,
+        old_value: i64,
+        new_value: i64,
+    },
+    /// The counter's lamports went to `recipient` and its data was wiped.
+    Closed {
+        counter: Pubkey,
+        recipient: Pubkey,
+        lamports: u64,
+    },
+}
+
+impl CounterEvent {
+    pub fn emit(&self) -> ProgramResult {
+        let data = borsh::to_vec(self)?;
+        sol_log_data(&[&data]);
+        Ok(())
+    }
+}
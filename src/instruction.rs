@@ -0,0 +1,804 @@
+//! Builders that assemble `CounterInstruction`s with the accounts each handler expects.
+
+use alloc::string::{ String, ToString };
+use solana_program::{
+    instruction::{ AccountMeta, Instruction },
+    pubkey::Pubkey,
+    system_program,
+};
+
+use crate::{
+    CounterInstruction,
+    OverflowPolicy,
+    find_counter_address,
+    find_indexed_counter,
+    find_registry_address,
+};
+
+/// Initializes a keypair counter; both `counter` and `payer` must sign.
+pub fn initialize_counter(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    payer: &Pubkey,
+    initial_value: u64
+) -> Instruction {
+    initialize_counter_with_max(program_id, counter, payer, initial_value, 0)
+}
+
+/// Initializes a keypair counter capped at `max_value` (0 for no cap).
+pub fn initialize_counter_with_max(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    payer: &Pubkey,
+    initial_value: u64,
+    max_value: u64
+) -> Instruction {
+    initialize_counter_with_bounds(program_id, counter, payer, initial_value, 0, max_value)
+}
+
+/// Initializes a keypair counter kept within `min_value..=max_value` (0 for no cap).
+pub fn initialize_counter_with_bounds(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    payer: &Pubkey,
+    initial_value: u64,
+    min_value: u64,
+    max_value: u64
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &(CounterInstruction::InitializeCounter {
+            initial_value,
+            max_value,
+            min_value,
+            name: String::new(),
+            on_overflow: OverflowPolicy::Error,
+        }).pack(),
+        vec![
+            AccountMeta::new(*counter, true),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false)
+        ]
+    )
+}
+
+/// Initializes a keypair counter unless it already exists; both `counter` and `payer` sign.
+pub fn initialize_counter_if_needed(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    payer: &Pubkey,
+    initial_value: u64
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &(CounterInstruction::InitializeIfNeeded { initial_value }).pack(),
+        vec![
+            AccountMeta::new(*counter, true),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false)
+        ]
+    )
+}
+
+/// Initializes a keypair counter labelled `name` (at most `MAX_NAME_LEN` bytes).
+pub fn initialize_named_counter(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    payer: &Pubkey,
+    initial_value: u64,
+    name: &str
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &(CounterInstruction::InitializeCounter {
+            initial_value,
+            max_value: 0,
+            min_value: 0,
+            name: name.to_string(),
+            on_overflow: OverflowPolicy::Error,
+        }).pack(),
+        vec![
+            AccountMeta::new(*counter, true),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false)
+        ]
+    )
+}
+
+/// Initializes the payer's PDA counter; only `payer` signs.
+pub fn initialize_pda_counter(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    initial_value: u64,
+    max_value: u64
+) -> Instruction {
+    let (counter, _) = find_counter_address(payer, program_id);
+    Instruction::new_with_bytes(
+        *program_id,
+        &(CounterInstruction::InitializeCounter {
+            initial_value,
+            max_value,
+            min_value: 0,
+            name: String::new(),
+            on_overflow: OverflowPolicy::Error,
+        }).pack(),
+        vec![
+            AccountMeta::new(counter, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false)
+        ]
+    )
+}
+
+/// Initializes a `CounterAccountU128`; both `counter` and `payer` must sign.
+pub fn initialize_counter_u128(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    payer: &Pubkey,
+    initial_value: u128
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &(CounterInstruction::InitializeCounterU128 { initial_value }).pack(),
+        vec![
+            AccountMeta::new(*counter, true),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false)
+        ]
+    )
+}
+
+/// Initializes a keypair counter that anyone may change unless `require_authority` is set;
+/// both `counter` and `payer` must sign.
+pub fn initialize_counter_with_access(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    payer: &Pubkey,
+    initial_value: u64,
+    require_authority: bool
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &(CounterInstruction::InitializeCounterWithAccess {
+            initial_value,
+            require_authority,
+        }).pack(),
+        vec![
+            AccountMeta::new(*counter, true),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false)
+        ]
+    )
+}
+
+/// Initializes a keypair counter whose increments handle overflow as `on_overflow` says;
+/// both `counter` and `payer` must sign.
+pub fn initialize_counter_with_overflow(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    payer: &Pubkey,
+    initial_value: u64,
+    on_overflow: OverflowPolicy
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &(CounterInstruction::InitializeCounter {
+            initial_value,
+            max_value: 0,
+            min_value: 0,
+            name: String::new(),
+            on_overflow,
+        }).pack(),
+        vec![
+            AccountMeta::new(*counter, true),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false)
+        ]
+    )
+}
+
+/// Checks that `initialize_counter` with the same accounts would succeed and returns the
+/// rent it would cost, without creating `counter`.
+pub fn simulate_initialize(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    payer: &Pubkey,
+    initial_value: u64
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &(CounterInstruction::SimulateInitialize { initial_value }).pack(),
+        vec![
+            AccountMeta::new(*counter, true),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false)
+        ]
+    )
+}
+
+/// Initializes the registry counter of `authority`, who also pays for it.
+pub fn initialize_registry_counter(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    initial_value: u64
+) -> Instruction {
+    let (counter, _) = find_registry_address(authority, program_id);
+    Instruction::new_with_bytes(
+        *program_id,
+        &(CounterInstruction::InitializeRegistryCounter { initial_value }).pack(),
+        vec![
+            AccountMeta::new(counter, false),
+            AccountMeta::new(*authority, true),
+            AccountMeta::new_readonly(system_program::id(), false)
+        ]
+    )
+}
+
+/// Initializes counter number `index` of `authority`, who also pays for it.
+pub fn initialize_indexed_counter(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    index: u64,
+    initial_value: u64
+) -> Instruction {
+    let (counter, _) = find_indexed_counter(authority, index, program_id);
+    Instruction::new_with_bytes(
+        *program_id,
+        &(CounterInstruction::InitializeIndexedCounter { initial_value, index }).pack(),
+        vec![
+            AccountMeta::new(counter, false),
+            AccountMeta::new(*authority, true),
+            AccountMeta::new_readonly(system_program::id(), false)
+        ]
+    )
+}
+
+/// Initializes `counter`, already created and owned by the program, for `authority`.
+pub fn initialize_data(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    initial_value: u64
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &(CounterInstruction::InitializeData { initial_value }).pack(),
+        vec![AccountMeta::new(*counter, true), AccountMeta::new_readonly(*authority, true)]
+    )
+}
+
+/// Initializes a `SignedCounterAccount`; both `counter` and `payer` must sign.
+pub fn initialize_signed_counter(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    payer: &Pubkey,
+    initial_value: i64
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &(CounterInstruction::InitializeSignedCounter { initial_value }).pack(),
+        vec![
+            AccountMeta::new(*counter, true),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false)
+        ]
+    )
+}
+
+pub fn increment_counter(program_id: &Pubkey, counter: &Pubkey, authority: &Pubkey) -> Instruction {
+    update_counter(program_id, counter, authority, &CounterInstruction::IncrementCounter)
+}
+
+pub fn step_counter(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    up: bool
+) -> Instruction {
+    update_counter(program_id, counter, authority, &(CounterInstruction::Step { up }))
+}
+
+pub fn decrement_counter(program_id: &Pubkey, counter: &Pubkey, authority: &Pubkey) -> Instruction {
+    update_counter(program_id, counter, authority, &CounterInstruction::DecrementCounter)
+}
+
+pub fn reset_counter(program_id: &Pubkey, counter: &Pubkey, authority: &Pubkey) -> Instruction {
+    update_counter(program_id, counter, authority, &CounterInstruction::ResetCounter)
+}
+
+pub fn set_counter(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    value: u64
+) -> Instruction {
+    update_counter(program_id, counter, authority, &(CounterInstruction::SetCounter { value }))
+}
+
+/// Sets `counter` to `new` only if it still holds `old`.
+pub fn set_checked(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    old: u64,
+    new: u64
+) -> Instruction {
+    update_counter(program_id, counter, authority, &(CounterInstruction::SetChecked { old, new }))
+}
+
+/// Moves `amount` from `source` to `destination`; `authority` must control both.
+pub fn transfer_count(
+    program_id: &Pubkey,
+    source: &Pubkey,
+    destination: &Pubkey,
+    authority: &Pubkey,
+    amount: u64
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &(CounterInstruction::TransferCount { amount }).pack(),
+        vec![
+            AccountMeta::new(*source, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(*authority, true)
+        ]
+    )
+}
+
+/// Adds `amount` to `counter`, saturating at `u64::MAX` instead of failing if `saturate`.
+pub fn add_checked(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    amount: u64,
+    saturate: bool
+) -> Instruction {
+    update_counter(
+        program_id,
+        counter,
+        authority,
+        &(CounterInstruction::AddChecked { amount, saturate })
+    )
+}
+
+/// Sets `counter` to `new_value` only if its count still equals `expected`.
+pub fn compare_and_set(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    expected: u64,
+    new_value: u64
+) -> Instruction {
+    update_counter(
+        program_id,
+        counter,
+        authority,
+        &(CounterInstruction::CompareAndSet { expected, new_value })
+    )
+}
+
+/// Moves the count of `counter` into `low..=high`.
+pub fn clamp_counter(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    low: u64,
+    high: u64
+) -> Instruction {
+    update_counter(program_id, counter, authority, &(CounterInstruction::ClampCounter { low, high }))
+}
+
+pub fn add_to_counter(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    amount: u64
+) -> Instruction {
+    update_counter(program_id, counter, authority, &(CounterInstruction::AddToCounter { amount }))
+}
+
+/// Same as `decrement_by`; sends `DecrementBy` rather than the deprecated
+/// `SubtractFromCounter` alias.
+pub fn subtract_from_counter(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    amount: u64
+) -> Instruction {
+    decrement_by(program_id, counter, authority, amount)
+}
+
+pub fn decrement_by(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    amount: u64
+) -> Instruction {
+    update_counter(program_id, counter, authority, &(CounterInstruction::DecrementBy { amount }))
+}
+
+pub fn decrement_exact(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    amount: u64
+) -> Instruction {
+    update_counter(program_id, counter, authority, &(CounterInstruction::DecrementExact { amount }))
+}
+
+pub fn weighted_increment(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    weight: u64
+) -> Instruction {
+    update_counter(
+        program_id,
+        counter,
+        authority,
+        &(CounterInstruction::WeightedIncrement { weight })
+    )
+}
+
+pub fn increment_if_elapsed(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    min_interval_secs: i64
+) -> Instruction {
+    update_counter(
+        program_id,
+        counter,
+        authority,
+        &(CounterInstruction::IncrementIfElapsed { min_interval_secs })
+    )
+}
+
+/// Sets the count, step and max value of `counter` atomically.
+pub fn configure(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    value: u64,
+    step: u64,
+    max_value: u64
+) -> Instruction {
+    update_counter(
+        program_id,
+        counter,
+        authority,
+        &(CounterInstruction::Configure { value, step, max_value })
+    )
+}
+
+/// Increments `counter` only if `other` holds at least `threshold`.
+pub fn increment_if_other(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    other: &Pubkey,
+    threshold: u64
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &(CounterInstruction::IncrementIfOther { threshold }).pack(),
+        vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(*other, false)
+        ]
+    )
+}
+
+pub fn multiply_counter(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    factor: u64
+) -> Instruction {
+    update_counter(
+        program_id,
+        counter,
+        authority,
+        &(CounterInstruction::MultiplyCounter { factor })
+    )
+}
+
+pub fn increment_many_times(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    times: u64
+) -> Instruction {
+    update_counter(
+        program_id,
+        counter,
+        authority,
+        &(CounterInstruction::IncrementManyTimes { times })
+    )
+}
+
+pub fn set_step(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    step: u64
+) -> Instruction {
+    update_counter(program_id, counter, authority, &(CounterInstruction::SetStep { step }))
+}
+
+pub fn set_wrapping(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    wrapping: bool
+) -> Instruction {
+    update_counter(program_id, counter, authority, &(CounterInstruction::SetWrapping { wrapping }))
+}
+
+pub fn set_modulus(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    modulus: u64
+) -> Instruction {
+    update_counter(program_id, counter, authority, &(CounterInstruction::SetModulus { modulus }))
+}
+
+/// Resets `counter` in place to `initial_value`; signed by its `authority`.
+pub fn reinitialize_counter(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    initial_value: u64
+) -> Instruction {
+    update_counter(
+        program_id,
+        counter,
+        authority,
+        &(CounterInstruction::Reinitialize { initial_value })
+    )
+}
+
+/// Permanently freezes `counter`; this cannot be undone.
+pub fn freeze_counter(program_id: &Pubkey, counter: &Pubkey, authority: &Pubkey) -> Instruction {
+    update_counter(program_id, counter, authority, &CounterInstruction::FreezeCounter)
+}
+
+pub fn pause_counter(program_id: &Pubkey, counter: &Pubkey, authority: &Pubkey) -> Instruction {
+    update_counter(program_id, counter, authority, &CounterInstruction::PauseCounter)
+}
+
+pub fn resume_counter(program_id: &Pubkey, counter: &Pubkey, authority: &Pubkey) -> Instruction {
+    update_counter(program_id, counter, authority, &CounterInstruction::ResumeCounter)
+}
+
+/// Permanently drops the authority of `counter`; signed by the current `authority`.
+pub fn renounce_authority(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey
+) -> Instruction {
+    update_counter(program_id, counter, authority, &CounterInstruction::RenounceAuthority)
+}
+
+/// Hands `counter` over to `new_authority`; signed by the current `authority`.
+pub fn transfer_authority(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    new_authority: &Pubkey
+) -> Instruction {
+    update_counter(
+        program_id,
+        counter,
+        authority,
+        &(CounterInstruction::TransferAuthority { new_authority: *new_authority })
+    )
+}
+
+/// Proposes `new_authority` for `counter`; nothing changes until it calls `accept_authority`.
+pub fn propose_authority(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    new_authority: &Pubkey
+) -> Instruction {
+    update_counter(
+        program_id,
+        counter,
+        authority,
+        &(CounterInstruction::ProposeAuthority { new_authority: *new_authority })
+    )
+}
+
+/// Makes `pending_authority`, which must sign, the authority of `counter`.
+pub fn accept_authority(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    pending_authority: &Pubkey
+) -> Instruction {
+    update_counter(program_id, counter, pending_authority, &CounterInstruction::AcceptAuthority)
+}
+
+/// Drops the pending authority proposal on `counter`.
+pub fn cancel_authority_proposal(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey
+) -> Instruction {
+    update_counter(program_id, counter, authority, &CounterInstruction::CancelAuthorityProposal)
+}
+
+/// Migrates a legacy count-only `counter`, which must sign; `payer` signs, funds the extra
+/// rent and becomes the authority.
+pub fn migrate_counter(program_id: &Pubkey, counter: &Pubkey, payer: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &CounterInstruction::MigrateCounter.pack(),
+        vec![
+            AccountMeta::new(*counter, true),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(system_program::id(), false)
+        ]
+    )
+}
+
+/// Reads the count of `counter` into return data; meant for simulation.
+pub fn get_count(program_id: &Pubkey, counter: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &CounterInstruction::GetCount.pack(),
+        vec![AccountMeta::new_readonly(*counter, false)]
+    )
+}
+
+/// `get_count` with the count returned big-endian when `big_endian` is set.
+pub fn read_count(program_id: &Pubkey, counter: &Pubkey, big_endian: bool) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &(CounterInstruction::ReadCount { big_endian }).pack(),
+        vec![AccountMeta::new_readonly(*counter, false)]
+    )
+}
+
+/// Reads how far `counter` is above `baseline` into return data; meant for simulation.
+pub fn count_since_baseline(program_id: &Pubkey, counter: &Pubkey, baseline: u64) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &(CounterInstruction::CountSinceBaseline { baseline }).pack(),
+        vec![AccountMeta::new_readonly(*counter, false)]
+    )
+}
+
+/// Fails unless `counter` holds exactly `expected`; lets a transaction enforce an invariant.
+pub fn assert_count(program_id: &Pubkey, counter: &Pubkey, expected: u64) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &(CounterInstruction::AssertCount { expected }).pack(),
+        vec![AccountMeta::new_readonly(*counter, false)]
+    )
+}
+
+/// Reads every field of `counter` into return data; meant for simulation.
+pub fn get_metadata(program_id: &Pubkey, counter: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &CounterInstruction::GetMetadata.pack(),
+        vec![AccountMeta::new_readonly(*counter, false)]
+    )
+}
+
+/// Closes `counter` and sends its lamports to `recipient`.
+pub fn close_counter(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    recipient: &Pubkey
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &CounterInstruction::CloseCounter.pack(),
+        vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*recipient, false)
+        ]
+    )
+}
+
+/// Decrements `counter` by 1 and, if it reaches 0, closes it with the rent going to
+/// `recipient`.
+pub fn decrement_and_maybe_close(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    recipient: &Pubkey
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &CounterInstruction::DecrementAndMaybeClose.pack(),
+        vec![
+            AccountMeta::new(*counter, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*recipient, false)
+        ]
+    )
+}
+
+/// Closes `counter` and returns its lamports to `authority`.
+pub fn close_counter_to_authority(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &CounterInstruction::CloseCounter.pack(),
+        vec![AccountMeta::new(*counter, false), AccountMeta::new(*authority, true)]
+    )
+}
+
+/// Increments each of `counters` by its step; all must be distinct and controlled by `authority`.
+pub fn batch_increment(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    counters: &[Pubkey]
+) -> Instruction {
+    let mut accounts = vec![AccountMeta::new_readonly(*authority, true)];
+    accounts.extend(counters.iter().map(|counter| AccountMeta::new(*counter, false)));
+    Instruction::new_with_bytes(*program_id, &CounterInstruction::BatchIncrement.pack(), accounts)
+}
+
+/// Sums the counts of `counters` into return data without modifying them.
+pub fn sum_counters(program_id: &Pubkey, counters: &[Pubkey]) -> Instruction {
+    let accounts = counters
+        .iter()
+        .map(|counter| AccountMeta::new_readonly(*counter, false))
+        .collect();
+    Instruction::new_with_bytes(*program_id, &CounterInstruction::SumCounters.pack(), accounts)
+}
+
+pub fn increment_counter_u128(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey
+) -> Instruction {
+    update_counter(program_id, counter, authority, &CounterInstruction::IncrementCounterU128)
+}
+
+pub fn add_to_counter_u128(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    amount: u128
+) -> Instruction {
+    update_counter(
+        program_id,
+        counter,
+        authority,
+        &(CounterInstruction::AddToCounterU128 { amount })
+    )
+}
+
+pub fn increment_signed(program_id: &Pubkey, counter: &Pubkey, authority: &Pubkey) -> Instruction {
+    update_counter(program_id, counter, authority, &CounterInstruction::IncrementSigned)
+}
+
+pub fn decrement_signed(program_id: &Pubkey, counter: &Pubkey, authority: &Pubkey) -> Instruction {
+    update_counter(program_id, counter, authority, &CounterInstruction::DecrementSigned)
+}
+
+/// Any instruction taking the writable counter followed by its signing authority.
+fn update_counter(
+    program_id: &Pubkey,
+    counter: &Pubkey,
+    authority: &Pubkey,
+    instruction: &CounterInstruction
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &instruction.pack(),
+        vec![AccountMeta::new(*counter, false), AccountMeta::new_readonly(*authority, true)]
+    )
+}
@@ -1,39 +1,447 @@
+// Handlers and types only need `core` and `alloc`, so every non-test build, host or SBF,
+// also checks that the program stays `no_std`. Unit tests link `std` for LiteSVM, so
+// `tests/no_std_build.rs` makes `cargo test` build the `no_std` library as well.
+#![cfg_attr(not(test), no_std)]
+
+#[macro_use]
+extern crate alloc;
+
+use alloc::{ string::String, vec::Vec };
 use borsh::{ BorshDeserialize, BorshSerialize };
 use solana_program::{
     account_info::{ AccountInfo, next_account_info },
-    entrypoint::{ ProgramResult, entrypoint },
+    entrypoint::ProgramResult,
     msg,
-    program::invoke,
+    program::{ invoke, invoke_signed, set_return_data },
     program_error::ProgramError,
     pubkey::Pubkey,
     system_instruction,
-    sysvar::{ Sysvar, rent::Rent },
+    system_program,
+    sysvar::{ Sysvar, clock::Clock, rent::Rent },
 };
 
-entrypoint!(process_instruction);
+#[cfg(not(target_os = "solana"))]
+pub mod client;
+pub mod cpi;
+pub mod event;
+pub mod instruction;
+pub mod zero_copy;
+
+use event::{ CounterEvent, CounterOperation };
+
+// Programs that depend on this crate for CPI enable `no-entrypoint` so the two don't clash.
+#[cfg(not(feature = "no-entrypoint"))]
+solana_program::entrypoint!(process_instruction);
 
 pub fn process_instruction(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
     instruction_data: &[u8]
 ) -> ProgramResult {
-    let instruction = CounterInstruction::try_from_slice(instruction_data).map_err(
-        |_| ProgramError::InvalidInstructionData
-    )?;
+    if instruction_data.len() > MAX_INSTRUCTION_LEN {
+        msg!(
+            "Instruction data is {} bytes, limit is {}",
+            instruction_data.len(),
+            MAX_INSTRUCTION_LEN
+        );
+        return Err(CounterError::InstructionTooLong.into());
+    }
+    let instruction = CounterInstruction::unpack(instruction_data)?;
+
+    // Development-only instrumentation for profiling compute usage; compiled out by default.
+    #[cfg(feature = "debug-logs")]
+    msg!(
+        "debug: {:?} with {} data bytes and {} accounts",
+        instruction,
+        instruction_data.len(),
+        accounts.len()
+    );
 
     match instruction {
-        CounterInstruction::InitializeCounter { initial_value } => {
-            process_initialize_counter(program_id, accounts, initial_value)?;
+        CounterInstruction::InitializeCounter {
+            initial_value,
+            max_value,
+            min_value,
+            name,
+            on_overflow,
+        } => {
+            process_initialize_counter(
+                program_id,
+                accounts,
+                initial_value,
+                max_value,
+                min_value,
+                &name,
+                true,
+                on_overflow
+            )?;
         }
         CounterInstruction::IncrementCounter => {
             process_increment_counter(program_id, accounts)?;
         }
+        CounterInstruction::DecrementCounter => {
+            process_decrement_counter(program_id, accounts)?;
+        }
+        CounterInstruction::ResetCounter => {
+            process_reset_counter(program_id, accounts)?;
+        }
+        CounterInstruction::SetCounter { value } => {
+            process_set_counter(program_id, accounts, value)?;
+        }
+        CounterInstruction::AddToCounter { amount } => {
+            process_add_to_counter(program_id, accounts, amount)?;
+        }
+        CounterInstruction::SubtractFromCounter { amount } => {
+            process_decrement_by(program_id, accounts, amount)?;
+        }
+        CounterInstruction::SetStep { step } => {
+            process_set_step(program_id, accounts, step)?;
+        }
+        CounterInstruction::PauseCounter => {
+            process_pause_counter(program_id, accounts)?;
+        }
+        CounterInstruction::ResumeCounter => {
+            process_resume_counter(program_id, accounts)?;
+        }
+        CounterInstruction::CloseCounter => {
+            process_close_counter(program_id, accounts)?;
+        }
+        CounterInstruction::InitializeCounterU128 { initial_value } => {
+            process_initialize_counter_u128(program_id, accounts, initial_value)?;
+        }
+        CounterInstruction::IncrementCounterU128 => {
+            process_add_to_counter_u128(program_id, accounts, 1, CounterOperation::Increment)?;
+        }
+        CounterInstruction::AddToCounterU128 { amount } => {
+            process_add_to_counter_u128(program_id, accounts, amount, CounterOperation::Add)?;
+        }
+        CounterInstruction::IncrementManyTimes { times } => {
+            process_increment_many(program_id, accounts, times)?;
+        }
+        CounterInstruction::InitializeSignedCounter { initial_value } => {
+            process_initialize_signed_counter(program_id, accounts, initial_value)?;
+        }
+        CounterInstruction::IncrementSigned => {
+            process_add_to_signed_counter(program_id, accounts, 1, CounterOperation::Increment)?;
+        }
+        CounterInstruction::DecrementSigned => {
+            process_add_to_signed_counter(program_id, accounts, -1, CounterOperation::Decrement)?;
+        }
+        CounterInstruction::TransferAuthority { new_authority } => {
+            process_transfer_authority(program_id, accounts, new_authority)?;
+        }
+        CounterInstruction::DecrementBy { amount } => {
+            process_decrement_by(program_id, accounts, amount)?;
+        }
+        CounterInstruction::MultiplyCounter { factor } => {
+            process_multiply_counter(program_id, accounts, factor)?;
+        }
+        CounterInstruction::MigrateCounter => {
+            process_migrate_counter(program_id, accounts)?;
+        }
+        CounterInstruction::CompareAndSet { expected, new_value } => {
+            process_compare_and_set(program_id, accounts, expected, new_value)?;
+        }
+        CounterInstruction::GetCount => {
+            process_read_count(program_id, accounts, false)?;
+        }
+        CounterInstruction::SetWrapping { wrapping } => {
+            process_set_wrapping(program_id, accounts, wrapping)?;
+        }
+        CounterInstruction::Reinitialize { initial_value } => {
+            process_reinitialize(program_id, accounts, initial_value)?;
+        }
+        CounterInstruction::ClampCounter { low, high } => {
+            process_clamp_counter(program_id, accounts, low, high)?;
+        }
+        CounterInstruction::BatchIncrement => {
+            process_batch_increment(program_id, accounts)?;
+        }
+        CounterInstruction::InitializeIfNeeded { initial_value } => {
+            process_initialize_if_needed(program_id, accounts, initial_value)?;
+        }
+        CounterInstruction::Step { up } => {
+            process_step(program_id, accounts, up)?;
+        }
+        CounterInstruction::FreezeCounter => {
+            process_freeze_counter(program_id, accounts)?;
+        }
+        CounterInstruction::AddChecked { amount, saturate } => {
+            process_add_checked(program_id, accounts, amount, saturate)?;
+        }
+        CounterInstruction::SumCounters => {
+            process_sum_counters(program_id, accounts)?;
+        }
+        CounterInstruction::InitializeData { initial_value } => {
+            process_initialize_data(program_id, accounts, initial_value)?;
+        }
+        CounterInstruction::InitializeRegistryCounter { initial_value } => {
+            process_initialize_registry_counter(program_id, accounts, initial_value)?;
+        }
+        CounterInstruction::DecrementExact { amount } => {
+            process_decrement_exact(program_id, accounts, amount)?;
+        }
+        CounterInstruction::ReadCount { big_endian } => {
+            process_read_count(program_id, accounts, big_endian)?;
+        }
+        CounterInstruction::SetModulus { modulus } => {
+            process_set_modulus(program_id, accounts, modulus)?;
+        }
+        CounterInstruction::SimulateInitialize { initial_value } => {
+            process_simulate_initialize(program_id, accounts, initial_value)?;
+        }
+        CounterInstruction::GetMetadata => {
+            process_get_metadata(program_id, accounts)?;
+        }
+        CounterInstruction::RenounceAuthority => {
+            process_renounce_authority(program_id, accounts)?;
+        }
+        CounterInstruction::WeightedIncrement { weight } => {
+            process_weighted_increment(program_id, accounts, weight)?;
+        }
+        CounterInstruction::IncrementIfElapsed { min_interval_secs } => {
+            process_increment_if_elapsed(program_id, accounts, min_interval_secs)?;
+        }
+        CounterInstruction::Configure { value, step, max_value } => {
+            process_configure(program_id, accounts, value, step, max_value)?;
+        }
+        CounterInstruction::CountSinceBaseline { baseline } => {
+            process_count_since_baseline(program_id, accounts, baseline)?;
+        }
+        CounterInstruction::IncrementIfOther { threshold } => {
+            process_increment_if_other(program_id, accounts, threshold)?;
+        }
+        CounterInstruction::DecrementAndMaybeClose => {
+            process_decrement_and_maybe_close(program_id, accounts)?;
+        }
+        CounterInstruction::AssertCount { expected } => {
+            process_assert_count(program_id, accounts, expected)?;
+        }
+        CounterInstruction::InitializeCounterWithAccess { initial_value, require_authority } => {
+            process_initialize_counter(
+                program_id,
+                accounts,
+                initial_value,
+                0,
+                0,
+                "",
+                require_authority,
+                OverflowPolicy::Error
+            )?;
+        }
+        CounterInstruction::SetChecked { old, new } => {
+            process_set_checked(program_id, accounts, old, new)?;
+        }
+        CounterInstruction::TransferCount { amount } => {
+            process_transfer_count(program_id, accounts, amount)?;
+        }
+        CounterInstruction::InitializeIndexedCounter { initial_value, index } => {
+            process_initialize_indexed_counter(program_id, accounts, initial_value, index)?;
+        }
+        CounterInstruction::ProposeAuthority { new_authority } => {
+            process_propose_authority(program_id, accounts, new_authority)?;
+        }
+        CounterInstruction::AcceptAuthority => {
+            process_accept_authority(program_id, accounts)?;
+        }
+        CounterInstruction::CancelAuthorityProposal => {
+            process_cancel_authority_proposal(program_id, accounts)?;
+        }
     }
 
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 fn process_initialize_counter(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    initial_value: u64,
+    max_value: u64,
+    min_value: u64,
+    name: &str,
+    require_authority: bool,
+    on_overflow: OverflowPolicy
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let counter_account = next_account_info(accounts_iter)?;
+    let payer_account = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+
+    if initial_value < min_value {
+        msg!("Initial value {} is below min value {}", initial_value, min_value);
+        return Err(CounterError::MinValueViolated.into());
+    }
+    if max_value != 0 && initial_value > max_value {
+        msg!("Initial value {} is above max value {}", initial_value, max_value);
+        return Err(CounterError::MaxValueExceeded.into());
+    }
+    let (name, name_len) = CounterAccount::pack_name(name)?;
+
+    let account_space = CounterAccount::SIZE as u64;
+
+    // A counter that doesn't sign must be the payer's counter PDA; the program signs for it.
+    let bump = if counter_account.is_signer {
+        0
+    } else {
+        assert_counter_pda(program_id, counter_account, &[COUNTER_SEED, payer_account.key.as_ref()])?
+    };
+    let bump_seed = [bump];
+    let pda_seeds: &[&[u8]] = &[COUNTER_SEED, payer_account.key.as_ref(), &bump_seed];
+    let signer_seeds: &[&[&[u8]]] = if counter_account.is_signer { &[] } else { &[pda_seeds] };
+
+    create_counter_account(
+        program_id,
+        counter_account,
+        payer_account,
+        system_program,
+        account_space,
+        signer_seeds
+    )?;
+
+    let clock = Clock::get()?;
+    let counter_data = CounterAccount {
+        authority: *payer_account.key,
+        last_updated: clock.unix_timestamp,
+        max_value,
+        bump,
+        min_value,
+        creator: *payer_account.key,
+        created_slot: clock.slot,
+        name_len,
+        name,
+        require_authority,
+        on_overflow,
+        ..CounterAccount::new(initial_value)
+    };
+    counter_data.pack(&mut counter_account.data.borrow_mut())?;
+
+    msg!("Counter initialized with value: {}", initial_value);
+
+    (CounterEvent::Initialized {
+        counter: *counter_account.key,
+        value: initial_value,
+    }).emit()
+}
+
+/// Creates the authority's canonical counter at its registry PDA.
+///
+/// The address depends only on the authority, so a second initialize for the same
+/// authority lands on the existing account and fails.
+fn process_initialize_registry_counter(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    initial_value: u64
+) -> ProgramResult {
+    let authority_account = accounts.get(1).ok_or(ProgramError::NotEnoughAccountKeys)?;
+    initialize_pda_counter(
+        program_id,
+        accounts,
+        initial_value,
+        &[REGISTRY_SEED, authority_account.key.as_ref()]
+    )?;
+
+    msg!("Registry counter initialized with value: {}", initial_value);
+
+    Ok(())
+}
+
+/// Creates one of the authority's numbered counters at `find_indexed_counter`, so a user
+/// can hold any number of counters without managing keypairs.
+fn process_initialize_indexed_counter(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    initial_value: u64,
+    index: u64
+) -> ProgramResult {
+    let authority_account = accounts.get(1).ok_or(ProgramError::NotEnoughAccountKeys)?;
+    initialize_pda_counter(
+        program_id,
+        accounts,
+        initial_value,
+        &[COUNTER_SEED, authority_account.key.as_ref(), &index.to_le_bytes()]
+    )?;
+
+    msg!("Indexed counter {} initialized with value: {}", index, initial_value);
+
+    Ok(())
+}
+
+/// Creates and writes a counter at the PDA derived from `seeds`, owned and paid for by the
+/// authority. Accounts: the counter, the authority and the system program.
+fn initialize_pda_counter(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    initial_value: u64,
+    seeds: &[&[u8]]
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let counter_account = next_account_info(accounts_iter)?;
+    let authority_account = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+
+    let bump = assert_counter_pda(program_id, counter_account, seeds)?;
+    let bump_seed = [bump];
+    let mut pda_seeds = seeds.to_vec();
+    pda_seeds.push(&bump_seed);
+
+    create_counter_account(
+        program_id,
+        counter_account,
+        authority_account,
+        system_program,
+        CounterAccount::SIZE as u64,
+        &[&pda_seeds]
+    )?;
+
+    let clock = Clock::get()?;
+    let counter_data = CounterAccount {
+        authority: *authority_account.key,
+        last_updated: clock.unix_timestamp,
+        bump,
+        creator: *authority_account.key,
+        created_slot: clock.slot,
+        ..CounterAccount::new(initial_value)
+    };
+    counter_data.pack(&mut counter_account.data.borrow_mut())?;
+
+    (CounterEvent::Initialized {
+        counter: *counter_account.key,
+        value: initial_value,
+    }).emit()
+}
+
+/// Runs `InitializeCounter` with no caps or name unless the counter already exists, in
+/// which case it only checks the existing account is a valid counter.
+fn process_initialize_if_needed(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    initial_value: u64
+) -> ProgramResult {
+    let counter_account = accounts.first().ok_or(ProgramError::NotEnoughAccountKeys)?;
+    if counter_account.owner == program_id && !counter_account.data_is_empty() {
+        let counter_data = CounterAccount::unpack(&counter_account.data.borrow())?;
+        msg!("Counter already initialized with value: {}", counter_data.count);
+        return Ok(());
+    }
+
+    process_initialize_counter(
+        program_id,
+        accounts,
+        initial_value,
+        0,
+        0,
+        "",
+        true,
+        OverflowPolicy::Error
+    )
+}
+
+/// Runs every `InitializeCounter` check against the accounts without creating anything,
+/// and returns the rent the counter would cost as return data.
+fn process_simulate_initialize(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
     initial_value: u64
@@ -44,12 +452,89 @@ fn process_initialize_counter(
     let payer_account = next_account_info(accounts_iter)?;
     let system_program = next_account_info(accounts_iter)?;
 
-    let account_space: u64 = 8;
+    if !counter_account.is_signer {
+        let seeds: &[&[u8]] = &[COUNTER_SEED, payer_account.key.as_ref()];
+        assert_counter_pda(program_id, counter_account, seeds)?;
+    }
+    let required_lamports = check_create_counter_account(
+        program_id,
+        counter_account,
+        payer_account,
+        system_program,
+        CounterAccount::SIZE as u64,
+        !counter_account.is_signer
+    )?;
+    if payer_account.lamports() < required_lamports {
+        msg!(
+            "Payer has {} lamports, initialize needs {}",
+            payer_account.lamports(),
+            required_lamports
+        );
+        return Err(ProgramError::InsufficientFunds);
+    }
+    set_return_data(&required_lamports.to_le_bytes());
+
+    msg!("Initialize with value {} would cost {} lamports", initial_value, required_lamports);
+
+    Ok(())
+}
+
+/// Checks the initialize accounts before a `account_space`-byte counter is created and
+/// returns the lamports it needs to be rent exempt.
+///
+/// Only PDA counters, which the program signs for, may skip the counter signature.
+fn check_create_counter_account(
+    program_id: &Pubkey,
+    counter_account: &AccountInfo,
+    payer_account: &AccountInfo,
+    system_program: &AccountInfo,
+    account_space: u64,
+    is_pda: bool
+) -> Result<u64, ProgramError> {
+    if payer_account.key == counter_account.key {
+        msg!("Payer and counter must be different accounts");
+        return Err(CounterError::DuplicateAccount.into());
+    }
+
+    if !payer_account.is_signer || (!is_pda && !counter_account.is_signer) {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if system_program.key != &system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    if counter_account.owner == program_id || !counter_account.data_is_empty() {
+        msg!("Counter already initialized");
+        return Err(CounterError::AlreadyInitialized.into());
+    }
+
+    Ok(Rent::get()?.minimum_balance(account_space as usize))
+}
 
-    let rent = Rent::get()?;
-    let required_lamports = rent.minimum_balance(account_space as usize);
+/// Validates the initialize accounts and allocates a program-owned, rent-exempt
+/// account of `account_space` bytes at `counter_account`.
+///
+/// `signer_seeds` is empty for keypair counters, which must sign themselves, and
+/// holds the PDA seeds otherwise.
+fn create_counter_account<'a>(
+    program_id: &Pubkey,
+    counter_account: &AccountInfo<'a>,
+    payer_account: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    account_space: u64,
+    signer_seeds: &[&[&[u8]]]
+) -> ProgramResult {
+    let required_lamports = check_create_counter_account(
+        program_id,
+        counter_account,
+        payer_account,
+        system_program,
+        account_space,
+        !signer_seeds.is_empty()
+    )?;
 
-    invoke(
+    invoke_signed(
         &system_instruction::create_account(
             payer_account.key,
             counter_account.key,
@@ -57,140 +542,6418 @@ fn process_initialize_counter(
             account_space,
             program_id
         ),
-        &[payer_account.clone(), counter_account.clone(), system_program.clone()]
+        &[payer_account.clone(), counter_account.clone(), system_program.clone()],
+        signer_seeds
     )?;
 
-    let counter_data = CounterAccount {
+    Ok(())
+}
+
+fn process_initialize_counter_u128(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    initial_value: u128
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let counter_account = next_account_info(accounts_iter)?;
+    let payer_account = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+
+    let account_space = CounterAccountU128::SIZE as u64;
+    create_counter_account(
+        program_id,
+        counter_account,
+        payer_account,
+        system_program,
+        account_space,
+        &[]
+    )?;
+
+    let counter_data = CounterAccountU128 {
         count: initial_value,
+        authority: *payer_account.key,
     };
 
     let mut account_data = &mut counter_account.data.borrow_mut()[..];
     counter_data.serialize(&mut account_data)?;
 
-    msg!("Counter initialized with value: {}", initial_value);
+    msg!("Wide counter initialized with value: {}", initial_value);
 
     Ok(())
 }
 
-fn process_increment_counter(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+fn process_initialize_signed_counter(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    initial_value: i64
+) -> ProgramResult {
     let accounts_iter = &mut accounts.iter();
 
     let counter_account = next_account_info(accounts_iter)?;
-    if counter_account.owner != program_id {
-        return Err(ProgramError::IncorrectProgramId);
-    }
+    let payer_account = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
 
-    let mut data = counter_account.data.borrow_mut();
-    let mut counter_data: CounterAccount = CounterAccount::try_from_slice(&data)?;
-    counter_data.count = counter_data.count.checked_add(1).ok_or(ProgramError::InvalidAccountData)?;
+    let account_space = SignedCounterAccount::SIZE as u64;
+    create_counter_account(
+        program_id,
+        counter_account,
+        payer_account,
+        system_program,
+        account_space,
+        &[]
+    )?;
 
-    counter_data.serialize(&mut &mut data[..])?;
+    let counter_data = SignedCounterAccount {
+        count: initial_value,
+        authority: *payer_account.key,
+    };
+
+    let mut account_data = &mut counter_account.data.borrow_mut()[..];
+    counter_data.serialize(&mut account_data)?;
 
-    msg!("Counter incremented to: {}", counter_data.count);
+    msg!("Signed counter initialized with value: {}", initial_value);
 
     Ok(())
 }
 
-#[derive(BorshDeserialize, BorshSerialize, Debug)]
-pub struct CounterAccount {
-    pub count: u64,
-}
+fn process_increment_counter(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let now = Clock::get()?.unix_timestamp;
+    update_counter_increment(program_id, accounts, CounterOperation::Increment, |counter_data| {
+        let applied = counter_data.increment_by_step()?;
 
-#[derive(BorshDeserialize, BorshSerialize, Debug)]
-pub enum CounterInstruction {
-    InitializeCounter {
-        initial_value: u64,
-    },
-    IncrementCounter,
+        msg!("Counter incremented to: {} at {}", counter_data.count, now);
+
+        Ok(applied)
+    })
 }
 
-#[cfg(test)]
-mod test {
-    use super::*;
-    use litesvm::{ LiteSVM };
-    use solana_sdk::{
-        account::ReadableAccount,
-        instruction::{ AccountMeta, Instruction },
-        message::Message,
-        signature::{ Keypair, Signer },
-        system_program,
-        transaction::Transaction,
-    };
+/// Increments by `step` only if the counter in the third account slot holds at least
+/// `threshold`, failing with `ConditionNotMet` otherwise.
+fn process_increment_if_other(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    threshold: u64
+) -> ProgramResult {
+    let other_account = accounts.get(2).ok_or(ProgramError::NotEnoughAccountKeys)?;
+    if other_account.owner != program_id {
+        msg!(
+            "Gating counter must be owned by {}, but is owned by {}",
+            program_id,
+            other_account.owner
+        );
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let other_count = CounterAccount::unpack(&other_account.data.borrow())?.count;
 
-    #[test]
-    fn test_counter_program() {
-        let mut svm = LiteSVM::new();
-        let payer = Keypair::new();
-        let counter_keypair = Keypair::new();
-        let initial_value: u64 = 42;
+    update_counter_increment(program_id, accounts, CounterOperation::Increment, |counter_data| {
+        if other_count < threshold {
+            msg!("Gating counter is at {}, below threshold {}", other_count, threshold);
+            return Err(CounterError::ConditionNotMet.into());
+        }
+        let new_count = counter_data.add_with_policy(counter_data.step)?;
+        counter_data.count = counter_data.check_max_value(new_count)?;
 
-        let program_keypair = Keypair::new();
-        let program_id = program_keypair.pubkey();
+        msg!("Counter incremented to: {}", counter_data.count);
 
-        svm.add_program_from_file(program_id, "target/deploy/counter_program.so").expect(
-            "Failed to load program"
-        );
+        Ok(counter_data.step)
+    })
+}
 
-        svm.airdrop(&payer.pubkey(), 1_000_000).expect("Failed to airdrop");
+/// Increments by 1 only if at least `min_interval_secs` have passed since the last
+/// update, failing with `TooSoon` otherwise.
+fn process_increment_if_elapsed(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    min_interval_secs: i64
+) -> ProgramResult {
+    let now = Clock::get()?.unix_timestamp;
+    update_counter_increment(program_id, accounts, CounterOperation::Increment, |counter_data| {
+        let elapsed = now.saturating_sub(counter_data.last_updated);
+        if elapsed < min_interval_secs {
+            msg!("Only {}s since the last update, need {}s", elapsed, min_interval_secs);
+            return Err(CounterError::TooSoon.into());
+        }
+        let new_count = counter_data.add_with_policy(1)?;
+        counter_data.count = counter_data.check_max_value(new_count)?;
 
-        println!("Testing counter initialization");
-        // msg!("Test message");
+        msg!("Counter incremented to: {} after {}s", counter_data.count, elapsed);
 
-        let init_instruction_data = borsh
-            ::to_vec(&(CounterInstruction::InitializeCounter { initial_value }))
-            .expect("Failed to serialize instruction");
-        let initialize_instruction = Instruction::new_with_bytes(
-            program_id,
-            &init_instruction_data,
-            vec![
-                AccountMeta::new(counter_keypair.pubkey(), true),
-                AccountMeta::new(payer.pubkey(), true),
-                AccountMeta::new_readonly(system_program::id(), false)
-            ]
-        );
+        Ok(1)
+    })
+}
 
-        let message = Message::new(&[initialize_instruction], Some(&payer.pubkey()));
-        let transaction = Transaction::new(
-            &[&payer, &counter_keypair],
-            message,
-            svm.latest_blockhash()
-        );
-        let result = svm.send_transaction(transaction);
-        assert!(result.is_ok(), "Initialize transaction should succeed");
+/// Applies `IncrementCounter` to every counter after the leading authority account.
+///
+/// Each counter goes through the same checks and settings as a single increment, so one
+/// bad account fails the whole batch. A counter listed twice fails with `DuplicateAccount`
+/// rather than being incremented twice.
+fn process_batch_increment(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let (authority_account, counter_accounts) = accounts
+        .split_first()
+        .ok_or(ProgramError::NotEnoughAccountKeys)?;
+    if counter_accounts.is_empty() {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
 
-        let logs = result.unwrap().logs;
+    for (index, counter_account) in counter_accounts.iter().enumerate() {
+        if counter_accounts[..index].iter().any(|seen| seen.key == counter_account.key) {
+            msg!("Counter {} is listed more than once", counter_account.key);
+            return Err(CounterError::DuplicateAccount.into());
+        }
+        let pair = [counter_account.clone(), authority_account.clone()];
+        update_counter_increment(program_id, &pair, CounterOperation::Increment, |counter_data| {
+            counter_data.increment_by_step()
+        })?;
+    }
+
+    msg!("Incremented {} counters", counter_accounts.len());
+
+    Ok(())
+}
+
+/// Moves `amount` from the first counter to the second, both under the trailing authority.
+///
+/// The source goes through `DecrementBy` and the destination through `AddToCounter`; if
+/// the add fails, the whole instruction fails and the runtime discards the subtraction.
+fn process_transfer_count(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let source_account = next_account_info(accounts_iter)?;
+    let destination_account = next_account_info(accounts_iter)?;
+    let authority_account = next_account_info(accounts_iter)?;
+    if source_account.key == destination_account.key {
+        msg!("Source and destination must be different counters");
+        return Err(CounterError::DuplicateAccount.into());
+    }
+
+    process_decrement_by(program_id, &[source_account.clone(), authority_account.clone()], amount)?;
+    process_add_to_counter(
+        program_id,
+        &[destination_account.clone(), authority_account.clone()],
+        amount
+    )?;
+
+    msg!("Transferred {} from {} to {}", amount, source_account.key, destination_account.key);
+
+    Ok(())
+}
+
+/// Behaves exactly like `IncrementCounter` when `up` is set and `DecrementCounter` otherwise.
+fn process_step(program_id: &Pubkey, accounts: &[AccountInfo], up: bool) -> ProgramResult {
+    if up {
+        process_increment_counter(program_id, accounts)
+    } else {
+        process_decrement_counter(program_id, accounts)
+    }
+}
+
+fn process_decrement_counter(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    update_counter(program_id, accounts, CounterOperation::Decrement, |counter_data| {
+        let new_count = counter_data.count.checked_sub(1).ok_or(CounterError::Underflow)?;
+        counter_data.count = counter_data.check_min_value(new_count)?;
+
+        msg!("Counter decremented to: {}", counter_data.count);
+
+        Ok(())
+    })
+}
+
+fn process_reset_counter(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    update_counter(program_id, accounts, CounterOperation::Reset, |counter_data| {
+        counter_data.count = 0;
+
+        msg!("Counter reset to: {}", counter_data.count);
+
+        Ok(())
+    })
+}
+
+fn process_set_counter(program_id: &Pubkey, accounts: &[AccountInfo], value: u64) -> ProgramResult {
+    update_counter(program_id, accounts, CounterOperation::Set, |counter_data| {
+        counter_data.count = value;
+
+        msg!("Counter set to: {}", value);
+
+        Ok(())
+    })
+}
+
+/// Like `SetCounter`, but fails with `StaleValue` unless the count is still `old`.
+fn process_set_checked(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    old: u64,
+    new: u64
+) -> ProgramResult {
+    update_counter(program_id, accounts, CounterOperation::Set, |counter_data| {
+        if counter_data.count != old {
+            msg!("Stale value: counter is {}, caller expected {}", counter_data.count, old);
+            return Err(CounterError::StaleValue.into());
+        }
+        counter_data.count = new;
+
+        msg!("Counter set from {} to {}", old, new);
+
+        Ok(())
+    })
+}
+
+/// Like `AddToCounter`, but with `saturate` an overflow stops at `u64::MAX` instead of
+/// failing.
+fn process_add_checked(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+    saturate: bool
+) -> ProgramResult {
+    update_counter_increment(program_id, accounts, CounterOperation::Add, |counter_data| {
+        let new_count = if saturate {
+            counter_data.count.saturating_add(amount)
+        } else {
+            add_or_overflow(counter_data.count, amount)?
+        };
+        counter_data.count = counter_data.check_max_value(new_count)?;
+
+        msg!("Added {} to counter, now: {}", amount, counter_data.count);
+
+        Ok(amount)
+    })
+}
+
+fn process_compare_and_set(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    expected: u64,
+    new_value: u64
+) -> ProgramResult {
+    update_counter(program_id, accounts, CounterOperation::CompareAndSet, |counter_data| {
+        if counter_data.count != expected {
+            msg!("Counter value {} does not match expected {}", counter_data.count, expected);
+            return Err(CounterError::CompareFailed.into());
+        }
+        counter_data.count = new_value;
+
+        msg!("Counter compared and set to: {}", new_value);
+
+        Ok(())
+    })
+}
+
+fn process_clamp_counter(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    low: u64,
+    high: u64
+) -> ProgramResult {
+    if low > high {
+        msg!("Invalid clamp range: {} > {}", low, high);
+        return Err(CounterError::InvalidRange.into());
+    }
+
+    update_counter(program_id, accounts, CounterOperation::Clamp, |counter_data| {
+        counter_data.count = counter_data.count.clamp(low, high);
+
+        msg!("Counter clamped to: {}", counter_data.count);
+
+        Ok(())
+    })
+}
+
+fn process_add_to_counter(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64
+) -> ProgramResult {
+    update_counter_increment(program_id, accounts, CounterOperation::Add, |counter_data| {
+        let new_count = add_or_overflow(counter_data.count, amount)?;
+        counter_data.count = counter_data.check_max_value(new_count)?;
+
+        msg!("Added {} to counter, now: {}", amount, counter_data.count);
+
+        Ok(amount)
+    })
+}
+
+fn process_multiply_counter(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    factor: u64
+) -> ProgramResult {
+    update_counter(program_id, accounts, CounterOperation::Multiply, |counter_data| {
+        let new_count = mul_or_overflow(counter_data.count, factor)?;
+        counter_data.count = counter_data.check_max_value(new_count)?;
+
+        msg!("Multiplied counter by {}, now: {}", factor, counter_data.count);
+
+        Ok(())
+    })
+}
+
+/// Adds `step * weight`, handling overflow as the counter's `on_overflow` says; under
+/// `OverflowPolicy::Error` it fails with `MulOverflow` or `AddOverflow`.
+fn process_weighted_increment(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    weight: u64
+) -> ProgramResult {
+    update_counter_increment(program_id, accounts, CounterOperation::Increment, |counter_data| {
+        let new_count = counter_data.add_steps_with_policy(weight)?;
+        counter_data.count = counter_data.check_max_value(new_count)?;
+
+        msg!(
+            "Counter incremented by {} x {}, now: {}",
+            counter_data.step,
+            weight,
+            counter_data.count
+        );
+
+        Ok(counter_data.step.saturating_mul(weight))
+    })
+}
+
+fn process_decrement_by(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64
+) -> ProgramResult {
+    update_counter(program_id, accounts, CounterOperation::Subtract, |counter_data| {
+        let new_count = counter_data.count.checked_sub(amount).ok_or_else(|| {
+            msg!("Cannot subtract {} from counter value {}", amount, counter_data.count);
+            CounterError::Underflow
+        })?;
+        counter_data.count = counter_data.check_min_value(new_count)?;
+
+        msg!("Subtracted {} from counter, now: {}", amount, counter_data.count);
+
+        Ok(())
+    })
+}
+
+/// Like `DecrementBy`, but a count below `amount` fails with `InsufficientBalance`
+/// so clients can tell a short balance apart from other failures.
+fn process_decrement_exact(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64
+) -> ProgramResult {
+    update_counter(program_id, accounts, CounterOperation::Subtract, |counter_data| {
+        if counter_data.count < amount {
+            msg!("Insufficient balance: {} is less than {}", counter_data.count, amount);
+            return Err(CounterError::InsufficientBalance.into());
+        }
+        counter_data.count = counter_data.check_min_value(counter_data.count - amount)?;
+
+        msg!("Subtracted exactly {} from counter, now: {}", amount, counter_data.count);
+
+        Ok(())
+    })
+}
+
+fn process_increment_many(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    times: u64
+) -> ProgramResult {
+    update_counter_increment(
+        program_id,
+        accounts,
+        CounterOperation::IncrementMany,
+        |counter_data| {
+            let new_count = counter_data.add_steps_with_policy(times)?;
+            counter_data.count = counter_data.check_max_value(new_count)?;
+
+            msg!("Counter incremented {} times to: {}", times, counter_data.count);
+
+            Ok(counter_data.step.saturating_mul(times))
+        }
+    )
+}
+
+fn process_set_step(program_id: &Pubkey, accounts: &[AccountInfo], step: u64) -> ProgramResult {
+    if step == 0 {
+        return Err(CounterError::InvalidStep.into());
+    }
+
+    update_counter_settings(program_id, accounts, CounterOperation::SetStep, |counter_data| {
+        counter_data.step = step;
+
+        msg!("Counter step set to: {}", step);
+
+        Ok(())
+    })
+}
+
+/// Sets the count, step and cap together so clients never observe a half-applied
+/// reconfiguration. Always needs the authority, even on public counters.
+fn process_configure(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    value: u64,
+    step: u64,
+    max_value: u64
+) -> ProgramResult {
+    if step == 0 {
+        return Err(CounterError::InvalidStep.into());
+    }
+    if max_value != 0 && value > max_value {
+        msg!("Counter value {} exceeds max value {}", value, max_value);
+        return Err(CounterError::MaxValueExceeded.into());
+    }
+
+    update_counter_settings(program_id, accounts, CounterOperation::Configure, |counter_data| {
+        counter_data.count = counter_data.check_min_value(value)?;
+        counter_data.step = step;
+        counter_data.max_value = max_value;
+
+        msg!("Counter configured: value {}, step {}, max value {}", value, step, max_value);
+
+        Ok(())
+    })
+}
+
+fn process_set_wrapping(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    wrapping: bool
+) -> ProgramResult {
+    update_counter_settings(program_id, accounts, CounterOperation::SetWrapping, |counter_data| {
+        counter_data.wrapping = wrapping;
+
+        msg!("Counter wrapping set to: {}", wrapping);
+
+        Ok(())
+    })
+}
+
+fn process_set_modulus(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    modulus: u64
+) -> ProgramResult {
+    update_counter_settings(program_id, accounts, CounterOperation::SetModulus, |counter_data| {
+        counter_data.modulus = modulus;
+
+        msg!("Counter modulus set to: {}", modulus);
+
+        Ok(())
+    })
+}
+
+/// Permanently blocks every later write to the counter; there is no thaw. Closing is
+/// still allowed so the rent can be recovered.
+fn process_freeze_counter(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    write_counter(program_id, accounts, CounterOperation::Freeze, false, |counter_data| {
+        counter_data.is_frozen = true;
+
+        msg!("Counter frozen at: {}", counter_data.count);
+
+        Ok(())
+    })
+}
+
+fn process_pause_counter(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    write_counter(program_id, accounts, CounterOperation::Pause, false, |counter_data| {
+        counter_data.is_paused = true;
+
+        msg!("Counter paused");
+
+        Ok(())
+    })
+}
+
+fn process_resume_counter(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    write_counter(program_id, accounts, CounterOperation::Resume, false, |counter_data| {
+        counter_data.is_paused = false;
+
+        msg!("Counter resumed");
+
+        Ok(())
+    })
+}
+
+fn process_transfer_authority(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    new_authority: Pubkey
+) -> ProgramResult {
+    if new_authority == Pubkey::default() {
+        msg!("New authority cannot be the default pubkey");
+        return Err(CounterError::InvalidAuthority.into());
+    }
+
+    write_counter(program_id, accounts, CounterOperation::TransferAuthority, false, |counter_data| {
+        counter_data.authority = new_authority;
+        counter_data.pending_authority = Pubkey::default();
+
+        msg!("Counter authority transferred to: {}", new_authority);
+
+        Ok(())
+    })
+}
+
+/// First step of a handoff: records `new_authority` as pending without changing who
+/// controls the counter until it signs `AcceptAuthority`.
+fn process_propose_authority(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    new_authority: Pubkey
+) -> ProgramResult {
+    if new_authority == Pubkey::default() {
+        msg!("New authority cannot be the default pubkey");
+        return Err(CounterError::InvalidAuthority.into());
+    }
+
+    write_counter(program_id, accounts, CounterOperation::ProposeAuthority, false, |counter_data| {
+        counter_data.pending_authority = new_authority;
+
+        msg!("Counter authority proposed: {}", new_authority);
+
+        Ok(())
+    })
+}
+
+/// Second step of a handoff, signed by the pending authority, which takes over.
+fn process_accept_authority(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    write_counter_as(
+        program_id,
+        accounts,
+        CounterOperation::TransferAuthority,
+        RequiredSigner::PendingAuthority,
+        |counter_data| {
+            counter_data.authority = counter_data.pending_authority;
+            counter_data.pending_authority = Pubkey::default();
+
+            msg!("Counter authority transferred to: {}", counter_data.authority);
+
+            Ok(())
+        }
+    )
+}
+
+/// Withdraws a proposal that hasn't been accepted yet.
+fn process_cancel_authority_proposal(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo]
+) -> ProgramResult {
+    write_counter(
+        program_id,
+        accounts,
+        CounterOperation::CancelAuthorityProposal,
+        false,
+        |counter_data| {
+            counter_data.pending_authority = Pubkey::default();
+
+            msg!("Counter authority proposal cancelled");
+
+            Ok(())
+        }
+    )
+}
+
+/// Clears the authority and `require_authority`, making the counter public for good:
+/// nobody can sign as the default pubkey, so no admin instruction can run afterwards.
+fn process_renounce_authority(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    write_counter(program_id, accounts, CounterOperation::RenounceAuthority, false, |counter_data| {
+        counter_data.authority = Pubkey::default();
+        counter_data.pending_authority = Pubkey::default();
+        counter_data.require_authority = false;
+
+        msg!("Counter authority renounced");
+
+        Ok(())
+    })
+}
+
+/// Logs the count and returns it as return data without writing anything; big-endian if
+/// `big_endian` is set, little-endian otherwise. Storage stays little-endian either way.
+fn process_read_count(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    big_endian: bool
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let counter_account = next_account_info(accounts_iter)?;
+    if counter_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let count = CounterAccount::unpack_count(&counter_account.data.borrow())?;
+    if big_endian {
+        set_return_data(&count.to_be_bytes());
+    } else {
+        set_return_data(&count.to_le_bytes());
+    }
+
+    msg!("Counter value: {}", count);
+
+    Ok(())
+}
+
+/// Returns how far the count is above `baseline`, or 0 if it isn't, as little-endian
+/// return data without writing anything.
+fn process_count_since_baseline(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    baseline: u64
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let counter_account = next_account_info(accounts_iter)?;
+    if counter_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let count = CounterAccount::unpack_count(&counter_account.data.borrow())?;
+    let delta = count.saturating_sub(baseline);
+    set_return_data(&delta.to_le_bytes());
+
+    msg!("Counter is {} above baseline {}", delta, baseline);
+
+    Ok(())
+}
+
+/// Fails with `AssertionFailed` unless the count equals `expected`; writes nothing.
+fn process_assert_count(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    expected: u64
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let counter_account = next_account_info(accounts_iter)?;
+    if counter_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let count = CounterAccount::unpack_count(&counter_account.data.borrow())?;
+    if count != expected {
+        msg!("Assertion failed: counter is {}, expected {}", count, expected);
+        return Err(CounterError::AssertionFailed.into());
+    }
+
+    Ok(())
+}
+
+/// Returns the whole Borsh-encoded `CounterAccount` as return data without writing anything.
+fn process_get_metadata(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let counter_account = next_account_info(accounts_iter)?;
+    if counter_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let data = counter_account.data.borrow();
+    CounterAccount::unpack(&data)?;
+    // `unpack` accepted the data, so it holds at least `SIZE` bytes; trailing ones are dropped.
+    let data = &data[..CounterAccount::SIZE];
+    set_return_data(data);
+
+    msg!("Returned {} bytes of counter metadata", data.len());
+
+    Ok(())
+}
+
+fn process_sum_counters(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    if accounts.is_empty() {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
+    let mut total: u64 = 0;
+    for counter_account in accounts {
+        if counter_account.owner != program_id {
+            msg!("Account {} is not a counter", counter_account.key);
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let count = CounterAccount::unpack_count(&counter_account.data.borrow())?;
+        total = total.checked_add(count).ok_or(CounterError::Overflow)?;
+    }
+    set_return_data(&total.to_le_bytes());
+
+    msg!("Sum of {} counters: {}", accounts.len(), total);
+
+    Ok(())
+}
+
+/// Shared path for instructions that modify an existing `CounterAccount`.
+///
+/// Rejects the mutation while the counter is paused and lets anyone run it on a counter
+/// without `require_authority`; see `write_counter` for the remaining checks.
+fn update_counter<F>(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    operation: CounterOperation,
+    update: F
+) -> ProgramResult
+    where F: FnOnce(&mut CounterAccount) -> ProgramResult
+{
+    write_counter(program_id, accounts, operation, true, |counter_data| {
+        if counter_data.is_paused {
+            msg!("Counter is paused");
+            return Err(CounterError::CounterPaused.into());
+        }
+        update(counter_data)
+    })
+}
+
+/// `update_counter` for the increments, whose `update` returns the amount it applied:
+/// `step`, the added amount or `step * times`. That amount, not the change in `count`, is
+/// added to `lifetime_total`, so increments that wrap or cycle back still count.
+fn update_counter_increment<F>(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    operation: CounterOperation,
+    update: F
+) -> ProgramResult
+    where F: FnOnce(&mut CounterAccount) -> Result<u64, ProgramError>
+{
+    update_counter(program_id, accounts, operation, |counter_data| {
+        let applied = update(counter_data)?;
+        // A metric, so it saturates rather than failing the write.
+        counter_data.lifetime_total = counter_data.lifetime_total.saturating_add(applied);
+        Ok(())
+    })
+}
+
+/// `update_counter` for instructions that change the counter's settings, which always need
+/// the authority: only the count of a counter without `require_authority` is public.
+fn update_counter_settings<F>(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    operation: CounterOperation,
+    update: F
+) -> ProgramResult
+    where F: FnOnce(&mut CounterAccount) -> ProgramResult
+{
+    write_counter(program_id, accounts, operation, false, |counter_data| {
+        if counter_data.is_paused {
+            msg!("Counter is paused");
+            return Err(CounterError::CounterPaused.into());
+        }
+        update(counter_data)
+    })
+}
+
+/// Loads a `CounterAccount`, applies `update` and writes it back.
+///
+/// Expects the counter followed by its authority, checks program ownership, writability,
+/// rent exemption, the authority signature and that the counter isn't frozen, runs
+/// `update` (which still sees the previous `last_updated`), stamps `last_updated` and
+/// `last_mutator`, bumps `update_count`, records the new count in `history` if `operation`
+/// can change it, writes the result back, returns the new `count` as little-endian return
+/// data and emits a `CounterEvent::Updated` for `operation`. Nothing is written if `update`
+/// fails.
+///
+/// With `allow_public` set, the authority check is skipped for counters initialized
+/// without `require_authority`; the second account must still sign.
+fn write_counter<F>(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    operation: CounterOperation,
+    allow_public: bool,
+    update: F
+) -> ProgramResult
+    where F: FnOnce(&mut CounterAccount) -> ProgramResult
+{
+    let signer = if allow_public {
+        RequiredSigner::AuthorityUnlessPublic
+    } else {
+        RequiredSigner::Authority
+    };
+    write_counter_as(program_id, accounts, operation, signer, update)
+}
+
+/// Whose signature `write_counter_as` expects on the account after the counter.
+#[derive(Clone, Copy)]
+enum RequiredSigner {
+    Authority,
+    /// The authority, or any signer for counters without `require_authority`.
+    AuthorityUnlessPublic,
+    /// The key proposed with `ProposeAuthority`.
+    PendingAuthority,
+}
+
+/// `write_counter` with an explicit choice of who has to sign.
+fn write_counter_as<F>(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    operation: CounterOperation,
+    signer: RequiredSigner,
+    update: F
+) -> ProgramResult
+    where F: FnOnce(&mut CounterAccount) -> ProgramResult
+{
+    let accounts_iter = &mut accounts.iter();
+
+    let counter_account = next_account_info(accounts_iter)?;
+    let authority_account = next_account_info(accounts_iter)?;
+    if counter_account.owner != program_id {
+        msg!(
+            "Counter account must be owned by {}, but is owned by {}",
+            program_id,
+            counter_account.owner
+        );
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    check_writable(counter_account)?;
+
+    check_rent_exempt(counter_account)?;
+
+    // Borrow discipline: decode into an owned struct under a short shared borrow, run
+    // `update` with no borrow held, then take the mutable borrow only for the write, so a
+    // CPI or second borrow added inside `update` can't hit a `RefCell` panic.
+    let mut counter_data = CounterAccount::unpack(&counter_account.data.borrow())?;
+    match signer {
+        RequiredSigner::AuthorityUnlessPublic if !counter_data.require_authority => {
+            // Anyone may write a public counter, but `last_mutator` has to name who did.
+            if !authority_account.is_signer {
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+        }
+        RequiredSigner::Authority | RequiredSigner::AuthorityUnlessPublic => {
+            check_authority(&counter_data.authority, authority_account)?;
+        }
+        // With nothing pending this expects the default key, which nobody can sign for.
+        RequiredSigner::PendingAuthority => {
+            check_authority(&counter_data.pending_authority, authority_account)?;
+        }
+    }
+    counter_data.check_not_frozen()?;
+
+    let old_value = counter_data.count;
+    update(&mut counter_data)?;
+    counter_data.last_updated = Clock::get()?.unix_timestamp;
+    counter_data.last_mutator = *authority_account.key;
+    counter_data.update_count = counter_data.update_count
+        .checked_add(1)
+        .ok_or(CounterError::Overflow)?;
+    if operation.changes_count() {
+        counter_data.record_history();
+    }
+
+    counter_data.pack(&mut counter_account.data.borrow_mut())?;
+    set_return_data(&counter_data.count.to_le_bytes());
+
+    #[cfg(feature = "debug-logs")]
+    msg!("debug: {:?} read and wrote {} counter bytes", operation, CounterAccount::SIZE);
+
+    (CounterEvent::Updated {
+        counter: *counter_account.key,
+        operation,
+        old_value,
+        new_value: counter_data.count,
+    }).emit()
+}
+
+fn process_close_counter(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let counter_account = next_account_info(accounts_iter)?;
+    let authority_account = next_account_info(accounts_iter)?;
+    // Without an explicit recipient the rent goes back to the authority.
+    let recipient_account = accounts_iter.next().unwrap_or(authority_account);
+    if counter_account.owner != program_id {
+        msg!("Refusing to close account {} owned by {}", counter_account.key, counter_account.owner);
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    check_writable(counter_account)?;
+
+    let counter_data = CounterAccount::unpack(&counter_account.data.borrow())?;
+    check_authority(&counter_data.authority, authority_account)?;
+    if !recipient_account.is_writable {
+        msg!("Recipient {} must be writable", recipient_account.key);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    close_counter_account(counter_account, recipient_account)
+}
+
+/// Decrements by 1 and, if that reaches 0, closes the counter in the same instruction.
+///
+/// Takes the same accounts as `CloseCounter`. Closing always needs the authority, even on
+/// public counters, since the recipient receives the rent.
+fn process_decrement_and_maybe_close(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo]
+) -> ProgramResult {
+    process_decrement_counter(program_id, accounts)?;
+
+    let accounts_iter = &mut accounts.iter();
+    let counter_account = next_account_info(accounts_iter)?;
+    let authority_account = next_account_info(accounts_iter)?;
+    let recipient_account = accounts_iter.next().unwrap_or(authority_account);
+
+    let counter_data = CounterAccount::unpack(&counter_account.data.borrow())?;
+    if counter_data.count != 0 {
+        return Ok(());
+    }
+    check_authority(&counter_data.authority, authority_account)?;
+    if !recipient_account.is_writable {
+        msg!("Recipient {} must be writable", recipient_account.key);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    close_counter_account(counter_account, recipient_account)
+}
+
+/// Moves every lamport of the already validated `counter_account` to `recipient_account`
+/// and hands the emptied account back to the system program, emitting `CounterEvent::Closed`.
+fn close_counter_account(
+    counter_account: &AccountInfo,
+    recipient_account: &AccountInfo
+) -> ProgramResult {
+    let counter_lamports = counter_account.lamports();
+    **recipient_account.lamports.borrow_mut() = recipient_account
+        .lamports()
+        .checked_add(counter_lamports)
+        .ok_or(CounterError::Overflow)?;
+    **counter_account.lamports.borrow_mut() = 0;
+
+    counter_account.data.borrow_mut().fill(0);
+    counter_account.realloc(0, false)?;
+    counter_account.assign(&system_program::id());
+
+    msg!("Counter closed, {} lamports sent to {}", counter_lamports, recipient_account.key);
+
+    (CounterEvent::Closed {
+        counter: *counter_account.key,
+        recipient: *recipient_account.key,
+        lamports: counter_lamports,
+    }).emit()
+}
+
+/// Rewrites an existing counter as if freshly initialized with `initial_value`.
+///
+/// Keeps the authority, PDA bump, provenance fields, `lifetime_total` and everything chosen
+/// at initialize (caps, name, `require_authority`, `on_overflow`), so `initial_value` has
+/// to fit the kept caps. `last_mutator` becomes the signing authority and everything else
+/// returns to its initialize defaults. Works on paused counters too.
+fn process_reinitialize(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    initial_value: u64
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let counter_account = next_account_info(accounts_iter)?;
+    let authority_account = next_account_info(accounts_iter)?;
+    if counter_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    check_writable(counter_account)?;
+
+    check_rent_exempt(counter_account)?;
+
+    let mut data = counter_account.data.borrow_mut();
+    let old_data = CounterAccount::unpack(&data)?;
+    check_authority(&old_data.authority, authority_account)?;
+    old_data.check_not_frozen()?;
+    old_data.check_max_value(initial_value)?;
+    old_data.check_min_value(initial_value)?;
+
+    let counter_data = CounterAccount {
+        authority: old_data.authority,
+        last_updated: Clock::get()?.unix_timestamp,
+        max_value: old_data.max_value,
+        bump: old_data.bump,
+        min_value: old_data.min_value,
+        creator: old_data.creator,
+        created_slot: old_data.created_slot,
+        name_len: old_data.name_len,
+        name: old_data.name,
+        require_authority: old_data.require_authority,
+        on_overflow: old_data.on_overflow,
+        last_mutator: *authority_account.key,
+        lifetime_total: old_data.lifetime_total,
+        ..CounterAccount::new(initial_value)
+    };
+    counter_data.pack(&mut data)?;
+    set_return_data(&counter_data.count.to_le_bytes());
+
+    msg!("Counter reinitialized with value: {}", initial_value);
+
+    (CounterEvent::Initialized {
+        counter: *counter_account.key,
+        value: initial_value,
+    }).emit()
+}
+
+/// Writes a fresh `CounterAccount` into an account the client already allocated.
+///
+/// The account must be program-owned, exactly `CounterAccount::SIZE` bytes, rent exempt
+/// and still zeroed. It has to sign so nobody else can claim it between the two steps.
+fn process_initialize_data(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    initial_value: u64
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let counter_account = next_account_info(accounts_iter)?;
+    let authority_account = next_account_info(accounts_iter)?;
+    if !counter_account.is_signer || !authority_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if counter_account.owner != program_id {
+        msg!(
+            "Counter account must be owned by {}, but is owned by {}",
+            program_id,
+            counter_account.owner
+        );
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    check_writable(counter_account)?;
+
+    if counter_account.data_len() != CounterAccount::SIZE {
+        msg!(
+            "Counter account has {} bytes, expected {}",
+            counter_account.data_len(),
+            CounterAccount::SIZE
+        );
+        return Err(CounterError::InvalidAccountSize.into());
+    }
+    check_rent_exempt(counter_account)?;
+
+    let mut data = counter_account.data.borrow_mut();
+    if data.iter().any(|&byte| byte != 0) {
+        msg!("Counter already initialized");
+        return Err(CounterError::AlreadyInitialized.into());
+    }
+
+    let clock = Clock::get()?;
+    let counter_data = CounterAccount {
+        authority: *authority_account.key,
+        last_updated: clock.unix_timestamp,
+        creator: *authority_account.key,
+        created_slot: clock.slot,
+        ..CounterAccount::new(initial_value)
+    };
+    counter_data.pack(&mut data)?;
+
+    msg!("Counter initialized with value: {}", initial_value);
+
+    (CounterEvent::Initialized {
+        counter: *counter_account.key,
+        value: initial_value,
+    }).emit()
+}
+
+/// Upgrades a legacy count-only account in place to the current `CounterAccount` layout.
+///
+/// Legacy counters carry no authority, so the counter's own keypair must sign to prove the
+/// caller controls it; the payer then becomes its authority and covers the extra rent for
+/// the larger account.
+fn process_migrate_counter(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let counter_account = next_account_info(accounts_iter)?;
+    let payer_account = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+
+    if !payer_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    // Legacy counters were keypair accounts, so only whoever holds the key may claim one.
+    if !counter_account.is_signer {
+        msg!("Legacy counter {} must sign its own migration", counter_account.key);
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if system_program.key != &system_program::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if counter_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    check_writable(counter_account)?;
+
+    if counter_account.data_len() != CounterAccount::LEGACY_SIZE {
+        msg!(
+            "Cannot migrate counter: expected {} bytes, got {}",
+            CounterAccount::LEGACY_SIZE,
+            counter_account.data_len()
+        );
+        return Err(CounterError::InvalidAccountSize.into());
+    }
+    let count = u64::try_from_slice(&counter_account.data.borrow())?;
+
+    let required_lamports = counter_rent(&Rent::get()?).saturating_sub(counter_account.lamports());
+    if required_lamports > 0 {
+        invoke(
+            &system_instruction::transfer(payer_account.key, counter_account.key, required_lamports),
+            &[payer_account.clone(), counter_account.clone(), system_program.clone()]
+        )?;
+    }
+
+    counter_account.realloc(CounterAccount::SIZE, false)?;
+
+    let clock = Clock::get()?;
+    let counter_data = CounterAccount {
+        authority: *payer_account.key,
+        last_updated: clock.unix_timestamp,
+        creator: *payer_account.key,
+        created_slot: clock.slot,
+        ..CounterAccount::new(count)
+    };
+    counter_data.pack(&mut counter_account.data.borrow_mut())?;
+
+    msg!("Counter migrated to version {} with value: {}", CURRENT_VERSION, count);
+
+    Ok(())
+}
+
+fn process_add_to_counter_u128(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u128,
+    operation: CounterOperation
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let counter_account = next_account_info(accounts_iter)?;
+    let authority_account = next_account_info(accounts_iter)?;
+    if counter_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    check_writable(counter_account)?;
+    check_rent_exempt(counter_account)?;
+
+    let mut data = counter_account.data.borrow_mut();
+    let mut counter_data: CounterAccountU128 = CounterAccountU128::try_from_slice(&data)?;
+    check_authority(&counter_data.authority, authority_account)?;
+    let old_value = counter_data.count;
+    counter_data.count = counter_data.count.checked_add(amount).ok_or(CounterError::Overflow)?;
+
+    counter_data.serialize(&mut &mut data[..])?;
+    set_return_data(&counter_data.count.to_le_bytes());
+
+    msg!("Added {} to wide counter, now: {}", amount, counter_data.count);
+
+    (CounterEvent::UpdatedU128 {
+        counter: *counter_account.key,
+        operation,
+        old_value,
+        new_value: counter_data.count,
+    }).emit()
+}
+
+fn process_add_to_signed_counter(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    delta: i64,
+    operation: CounterOperation
+) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+
+    let counter_account = next_account_info(accounts_iter)?;
+    let authority_account = next_account_info(accounts_iter)?;
+    if counter_account.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    check_writable(counter_account)?;
+    check_rent_exempt(counter_account)?;
+
+    let mut data = counter_account.data.borrow_mut();
+    let mut counter_data: SignedCounterAccount = SignedCounterAccount::try_from_slice(&data)?;
+    check_authority(&counter_data.authority, authority_account)?;
+    let old_value = counter_data.count;
+    counter_data.count = counter_data.count.checked_add(delta).ok_or(if delta < 0 {
+        CounterError::Underflow
+    } else {
+        CounterError::Overflow
+    })?;
+
+    counter_data.serialize(&mut &mut data[..])?;
+    set_return_data(&counter_data.count.to_le_bytes());
+
+    msg!("Signed counter changed by {}, now: {}", delta, counter_data.count);
+
+    (CounterEvent::UpdatedSigned {
+        counter: *counter_account.key,
+        operation,
+        old_value,
+        new_value: counter_data.count,
+    }).emit()
+}
+
+/// Rejects a counter passed read-only, before any data is borrowed mutably.
+fn check_writable(counter_account: &AccountInfo) -> ProgramResult {
+    if !counter_account.is_writable {
+        msg!("Counter account must be writable");
+        return Err(ProgramError::InvalidArgument);
+    }
+    Ok(())
+}
+
+/// Rejects writes to a counter whose lamports no longer cover rent exemption.
+fn check_rent_exempt(counter_account: &AccountInfo) -> ProgramResult {
+    if !Rent::get()?.is_exempt(counter_account.lamports(), counter_account.data_len()) {
+        msg!("Counter account is not rent exempt");
+        return Err(CounterError::NotRentExempt.into());
+    }
+    Ok(())
+}
+
+/// `checked_add` for the arithmetic instructions, reporting overflow as `AddOverflow`.
+fn add_or_overflow(lhs: u64, rhs: u64) -> Result<u64, ProgramError> {
+    lhs.checked_add(rhs).ok_or_else(|| {
+        msg!("Addition overflow: {} + {}", lhs, rhs);
+        CounterError::AddOverflow.into()
+    })
+}
+
+/// `checked_mul` for the arithmetic instructions, reporting overflow as `MulOverflow`.
+fn mul_or_overflow(lhs: u64, rhs: u64) -> Result<u64, ProgramError> {
+    lhs.checked_mul(rhs).ok_or_else(|| {
+        msg!("Multiplication overflow: {} * {}", lhs, rhs);
+        CounterError::MulOverflow.into()
+    })
+}
+
+/// Ensures the stored authority has signed the instruction.
+fn check_authority(authority: &Pubkey, authority_account: &AccountInfo) -> ProgramResult {
+    if !authority_account.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if authority_account.key != authority {
+        msg!("Authority mismatch: expected {}, got {}", authority, authority_account.key);
+        return Err(CounterError::Unauthorized.into());
+    }
+    Ok(())
+}
+
+/// Seed prefix for counter PDAs, followed by the payer's key and, for indexed counters,
+/// the little-endian index.
+pub const COUNTER_SEED: &[u8] = b"counter";
+
+/// Derives the PDA counter address owned by `payer` and its bump seed.
+pub fn find_counter_address(payer: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[COUNTER_SEED, payer.as_ref()], program_id)
+}
+
+/// Derives the address of `authority`'s counter number `index` and its bump seed.
+pub fn find_indexed_counter(authority: &Pubkey, index: u64, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[COUNTER_SEED, authority.as_ref(), &index.to_le_bytes()],
+        program_id
+    )
+}
+
+/// Seed prefix for registry PDAs, followed by the authority's key.
+pub const REGISTRY_SEED: &[u8] = b"registry";
+
+/// Derives the address of the single registry counter `authority` may own.
+pub fn find_registry_address(authority: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[REGISTRY_SEED, authority.as_ref()], program_id)
+}
+
+/// Checks that `counter_account` is the PDA derived from `seeds` and returns its bump.
+pub fn assert_counter_pda(
+    program_id: &Pubkey,
+    counter_account: &AccountInfo,
+    seeds: &[&[u8]]
+) -> Result<u8, ProgramError> {
+    let (expected, bump) = Pubkey::find_program_address(seeds, program_id);
+    if counter_account.key != &expected {
+        msg!("Invalid counter PDA: expected {}, got {}", expected, counter_account.key);
+        return Err(ProgramError::InvalidSeeds);
+    }
+    Ok(bump)
+}
+
+/// Layout version written as the first byte of every `CounterAccount`.
+pub const CURRENT_VERSION: u8 = 1;
+
+#[derive(BorshDeserialize, BorshSerialize, Debug, PartialEq, Eq)]
+pub struct CounterAccount {
+    /// Layout version; always the first byte of the account data.
+    pub version: u8,
+    pub count: u64,
+    /// The only key allowed to mutate the counter; set to the payer on initialize.
+    pub authority: Pubkey,
+    /// Unix timestamp of the last initialize or mutation.
+    pub last_updated: i64,
+    /// Number of successful mutations since initialize.
+    pub update_count: u64,
+    /// Amount added by each `IncrementCounter`; starts at 1.
+    pub step: u64,
+    /// While set, every mutation other than `ResumeCounter` is rejected.
+    pub is_paused: bool,
+    /// Ceiling enforced by increment and add; 0 means no cap.
+    pub max_value: u64,
+    /// Bump seed of the counter PDA, or 0 for keypair counters.
+    pub bump: u8,
+    /// Floor enforced by decrement and subtract; 0 means no floor.
+    pub min_value: u64,
+    /// Payer that created the counter; never changes.
+    pub creator: Pubkey,
+    /// Slot the counter was created in (or migrated, for legacy counters); never changes.
+    pub created_slot: u64,
+    /// Ring buffer of the count after each of the last `HISTORY_LEN` mutations.
+    pub history: [u64; HISTORY_LEN],
+    /// Index in `history` the next mutation writes to, which is also the oldest entry.
+    pub head: u8,
+    /// Number of bytes of `name` in use.
+    pub name_len: u8,
+    /// UTF-8 label chosen at initialize, zero-padded to `MAX_NAME_LEN`.
+    pub name: [u8; MAX_NAME_LEN],
+    /// When set, `IncrementCounter` wraps past `u64::MAX` whatever `on_overflow` says; off
+    /// by default.
+    pub wrapping: bool,
+    /// Once set by `FreezeCounter`, every write is rejected; it can never be cleared.
+    pub is_frozen: bool,
+    /// When non-zero, `IncrementCounter` computes `(count + step) % modulus`, cycling back
+    /// to 0; takes precedence over `wrapping`. 0 disables it.
+    pub modulus: u64,
+    /// When cleared, anyone may change the count; settings, pause, freeze, authority
+    /// transfer, reinitialize and close still need the authority. Set by default; cleared for good
+    /// by `RenounceAuthority`.
+    pub require_authority: bool,
+    /// What the increment instructions do when the count would pass `u64::MAX`; chosen at
+    /// initialize and never changed.
+    pub on_overflow: OverflowPolicy,
+    /// Signer of the most recent mutation; the default key until the first one.
+    pub last_mutator: Pubkey,
+    /// Sum of everything added by increments and adds since initialize; decrements, resets
+    /// and reinitialize never lower it, and sets or multiplies don't raise it.
+    pub lifetime_total: u64,
+    /// Authority proposed by `ProposeAuthority` and not yet accepted; the default key when
+    /// no handoff is in progress.
+    pub pending_authority: Pubkey,
+}
+
+/// How a counter's increments handle overflowing `u64::MAX`; stored as one byte.
+#[derive(BorshDeserialize, BorshSerialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Fail, leaving the count unchanged: `Overflow` for a single step, `AddOverflow` or
+    /// `MulOverflow` for `IncrementManyTimes` and `WeightedIncrement`.
+    #[default]
+    Error,
+    /// Stop at `u64::MAX`.
+    Saturate,
+    /// Continue from 0.
+    Wrap,
+}
+
+/// Number of past counts kept in `CounterAccount::history`.
+pub const HISTORY_LEN: usize = 8;
+
+/// Longest counter name, in bytes.
+pub const MAX_NAME_LEN: usize = 32;
+
+/// Length of the account discriminator, the version byte that leads every `CounterAccount`.
+pub const DISCRIMINATOR_LEN: usize = 1;
+
+/// Byte offset of `count` in the serialized account, right after the discriminator.
+pub const COUNT_OFFSET: usize = DISCRIMINATOR_LEN;
+
+/// Bytes to allocate for a `CounterAccount`, e.g. when computing rent off-chain.
+pub const COUNTER_ACCOUNT_SIZE: usize = CounterAccount::SIZE;
+
+/// Lamports a `CounterAccount` needs to be rent exempt, i.e. what initialize charges the payer.
+pub fn counter_rent(rent: &Rent) -> u64 {
+    rent.minimum_balance(COUNTER_ACCOUNT_SIZE)
+}
+
+/// A freshly initialized, empty counter: current version, step 1 and no caps.
+impl Default for CounterAccount {
+    fn default() -> Self {
+        Self {
+            version: CURRENT_VERSION,
+            count: 0,
+            authority: Pubkey::default(),
+            last_updated: 0,
+            update_count: 0,
+            step: 1,
+            is_paused: false,
+            max_value: 0,
+            bump: 0,
+            min_value: 0,
+            creator: Pubkey::default(),
+            created_slot: 0,
+            history: [0; HISTORY_LEN],
+            head: 0,
+            name_len: 0,
+            name: [0; MAX_NAME_LEN],
+            wrapping: false,
+            is_frozen: false,
+            modulus: 0,
+            require_authority: true,
+            on_overflow: OverflowPolicy::Error,
+            last_mutator: Pubkey::default(),
+            lifetime_total: 0,
+            pending_authority: Pubkey::default(),
+        }
+    }
+}
+
+impl CounterAccount {
+    /// Creates a counter holding `count`, with every other field at its default.
+    pub fn new(count: u64) -> Self {
+        Self { count, ..Self::default() }
+    }
+
+    /// Serialized size: version + count + authority + last_updated + update_count + step
+    /// + is_paused + max_value + bump + min_value + creator + created_slot + history + head
+    /// + name_len + name + wrapping + is_frozen + modulus + require_authority + on_overflow
+    /// + last_mutator + lifetime_total + pending_authority.
+    pub const SIZE: usize =
+        1 + 8 + 32 + 8 + 8 + 8 + 1 + 8 + 1 + 8 + 32 + 8 + 8 * HISTORY_LEN + 1 + 1 + MAX_NAME_LEN
+            + 1 + 1 + 8 + 1 + 1 + 32 + 8 + 32;
+
+    /// Size of the original layout, which held only `count`.
+    pub const LEGACY_SIZE: usize = 8;
+
+    /// Deserializes a counter after the checks of `check_header`.
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        Ok(Self::try_from_slice(Self::check_header(data)?)?)
+    }
+
+    /// Returns the first `SIZE` bytes of `data` once they look like a current counter,
+    /// rejecting data shorter than `SIZE` or written with an unknown layout version. Bytes
+    /// past `SIZE`, e.g. in an over-allocated account, are ignored. Legacy count-only data
+    /// gets its own error pointing at `MigrateCounter`, and a zero version byte, i.e.
+    /// allocated but never initialized data, is reported as `UninitializedAccount`.
+    pub fn check_header(data: &[u8]) -> Result<&[u8], ProgramError> {
+        if data.len() == Self::LEGACY_SIZE {
+            msg!("Legacy counter account must be upgraded with MigrateCounter");
+            return Err(CounterError::LegacyAccountNeedsMigration.into());
+        }
+        let Some(data) = data.get(..Self::SIZE) else {
+            msg!("Invalid counter account size: expected {}, got {}", Self::SIZE, data.len());
+            return Err(CounterError::InvalidAccountSize.into());
+        };
+        match data.first() {
+            Some(&CURRENT_VERSION) => {}
+            Some(0) => {
+                msg!("Counter account is not initialized");
+                return Err(CounterError::UninitializedAccount.into());
+            }
+            version => {
+                msg!("Unsupported counter account version: {:?}", version);
+                return Err(CounterError::UnsupportedVersion.into());
+            }
+        }
+        Ok(data)
+    }
+
+    /// `unpack(data)?.count` without decoding the rest; handlers that only need the count
+    /// use this to save compute.
+    pub fn unpack_count(data: &[u8]) -> Result<u64, ProgramError> {
+        Self::read_count(Self::check_header(data)?)
+    }
+
+    /// Serializes the counter into the first `SIZE` bytes of `dst`, leaving any bytes after
+    /// them untouched. The inverse of `unpack`; handlers write counters through this rather
+    /// than Borsh directly.
+    pub fn pack(&self, dst: &mut [u8]) -> ProgramResult {
+        let dst_len = dst.len();
+        let Some(mut dst) = dst.get_mut(..Self::SIZE) else {
+            msg!("Invalid counter account size: expected {}, got {}", Self::SIZE, dst_len);
+            return Err(CounterError::InvalidAccountSize.into());
+        };
+        self.serialize(&mut dst)?;
+        Ok(())
+    }
+
+    /// Reads `count` directly from account data, skipping a full Borsh decode.
+    ///
+    /// Does not validate the version byte; use `unpack_count` on account data that might
+    /// not be a current counter. There is deliberately no in-place writer: every mutation
+    /// also stamps `last_updated`, `last_mutator` and `update_count`, records history and
+    /// emits the full event, so writes always go through `pack`.
+    pub fn read_count(data: &[u8]) -> Result<u64, ProgramError> {
+        let bytes = data
+            .get(COUNT_OFFSET..COUNT_OFFSET + 8)
+            .ok_or(ProgramError::AccountDataTooSmall)?;
+        let mut count = [0u8; 8];
+        count.copy_from_slice(bytes);
+        Ok(u64::from_le_bytes(count))
+    }
+
+    /// One `IncrementCounter`: adds `step`, cycling at `modulus` if set and otherwise
+    /// handling overflow as `add_with_policy` does, then enforces `max_value`. Returns the
+    /// step applied.
+    fn increment_by_step(&mut self) -> Result<u64, ProgramError> {
+        let new_count = if self.modulus != 0 {
+            // Computed in u128 so `count + step` cannot overflow before the reduction.
+            let sum = (self.count as u128) + (self.step as u128);
+            (sum % (self.modulus as u128)) as u64
+        } else {
+            self.add_with_policy(self.step)?
+        };
+        self.count = self.check_max_value(new_count)?;
+        Ok(self.step)
+    }
+
+    /// Adds `step * times` for the multi-step increments, handling an overflow of the
+    /// product or the sum as `add_with_policy` does, except that `OverflowPolicy::Error`
+    /// reports `MulOverflow` or `AddOverflow`.
+    fn add_steps_with_policy(&self, times: u64) -> Result<u64, ProgramError> {
+        if self.wrapping || self.on_overflow == OverflowPolicy::Wrap {
+            return Ok(self.count.wrapping_add(self.step.wrapping_mul(times)));
+        }
+        if self.on_overflow == OverflowPolicy::Saturate {
+            return Ok(self.count.saturating_add(self.step.saturating_mul(times)));
+        }
+        add_or_overflow(self.count, mul_or_overflow(self.step, times)?)
+    }
+
+    /// Adds `amount` to the count, handling overflow as `wrapping` and `on_overflow` say.
+    fn add_with_policy(&self, amount: u64) -> Result<u64, ProgramError> {
+        if let Some(new_count) = self.count.checked_add(amount) {
+            return Ok(new_count);
+        }
+        match self.on_overflow {
+            _ if self.wrapping => Ok(self.count.wrapping_add(amount)),
+            OverflowPolicy::Error => Err(CounterError::Overflow.into()),
+            OverflowPolicy::Saturate => Ok(u64::MAX),
+            OverflowPolicy::Wrap => Ok(self.count.wrapping_add(amount)),
+        }
+    }
+
+    /// Rejects `new_count` if it would exceed a configured `max_value`.
+    fn check_max_value(&self, new_count: u64) -> Result<u64, ProgramError> {
+        if self.max_value != 0 && new_count > self.max_value {
+            msg!("Counter value {} exceeds max value {}", new_count, self.max_value);
+            return Err(CounterError::MaxValueExceeded.into());
+        }
+        Ok(new_count)
+    }
+
+    /// Returns the counter's name, empty if none was given.
+    pub fn name(&self) -> &str {
+        core::str::from_utf8(&self.name[..self.name_len as usize]).unwrap_or_default()
+    }
+
+    /// Copies `name` into a fixed-size buffer, rejecting names over `MAX_NAME_LEN` bytes.
+    fn pack_name(name: &str) -> Result<([u8; MAX_NAME_LEN], u8), ProgramError> {
+        if name.len() > MAX_NAME_LEN {
+            msg!("Counter name is {} bytes, max is {}", name.len(), MAX_NAME_LEN);
+            return Err(CounterError::NameTooLong.into());
+        }
+        let mut packed = [0; MAX_NAME_LEN];
+        packed[..name.len()].copy_from_slice(name.as_bytes());
+        Ok((packed, name.len() as u8))
+    }
+
+    /// Returns the recorded counts from oldest to newest, with zeros for unused slots.
+    pub fn recent_history(&self) -> [u64; HISTORY_LEN] {
+        let mut history = self.history;
+        history.rotate_left(self.head as usize);
+        history
+    }
+
+    /// Writes the current count at `head` and advances it, wrapping around.
+    fn record_history(&mut self) {
+        self.history[self.head as usize] = self.count;
+        self.head = ((self.head as usize + 1) % HISTORY_LEN) as u8;
+    }
+
+    /// Rejects any write to a frozen counter.
+    fn check_not_frozen(&self) -> ProgramResult {
+        if self.is_frozen {
+            msg!("Counter is frozen");
+            return Err(CounterError::CounterFrozen.into());
+        }
+        Ok(())
+    }
+
+    /// Rejects `new_count` if it would drop below the configured `min_value`.
+    fn check_min_value(&self, new_count: u64) -> Result<u64, ProgramError> {
+        if new_count < self.min_value {
+            msg!("Counter value {} is below min value {}", new_count, self.min_value);
+            return Err(CounterError::MinValueViolated.into());
+        }
+        Ok(new_count)
+    }
+}
+
+/// A counter with a 16-byte count, for clients that would overflow `u64`.
+#[derive(BorshDeserialize, BorshSerialize, Debug)]
+pub struct CounterAccountU128 {
+    pub count: u128,
+    pub authority: Pubkey,
+}
+
+impl CounterAccountU128 {
+    /// Serialized size: count + authority.
+    pub const SIZE: usize = 16 + 32;
+}
+
+/// A counter that can go negative, bounded by `i64::MIN` and `i64::MAX`.
+#[derive(BorshDeserialize, BorshSerialize, Debug)]
+pub struct SignedCounterAccount {
+    pub count: i64,
+    pub authority: Pubkey,
+}
+
+impl SignedCounterAccount {
+    /// Serialized size: count + authority.
+    pub const SIZE: usize = 8 + 32;
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Debug)]
+pub enum CounterInstruction {
+    InitializeCounter {
+        initial_value: u64,
+        /// Upper bound for the count; 0 disables the cap.
+        max_value: u64,
+        /// Lower bound for the count; must not exceed `initial_value`.
+        min_value: u64,
+        /// Label for display, at most `MAX_NAME_LEN` bytes; may be empty.
+        name: String,
+        /// How increments handle overflowing `u64::MAX`.
+        on_overflow: OverflowPolicy,
+    },
+    IncrementCounter,
+    DecrementCounter,
+    ResetCounter,
+    SetCounter {
+        value: u64,
+    },
+    AddToCounter {
+        amount: u64,
+    },
+    /// Deprecated alias of `DecrementBy`, kept only so its wire tag stays decodable; new
+    /// clients should send `DecrementBy`.
+    SubtractFromCounter {
+        amount: u64,
+    },
+    SetStep {
+        step: u64,
+    },
+    PauseCounter,
+    ResumeCounter,
+    /// Accounts: the counter, its authority, then an optional recipient for the rent,
+    /// which defaults to the (writable) authority.
+    CloseCounter,
+    /// Creates a `CounterAccountU128` instead of a `CounterAccount`.
+    InitializeCounterU128 {
+        initial_value: u128,
+    },
+    IncrementCounterU128,
+    AddToCounterU128 {
+        amount: u128,
+    },
+    /// Applies `times` increments of `step` in a single write.
+    IncrementManyTimes {
+        times: u64,
+    },
+    /// Creates a `SignedCounterAccount`, which may go below zero.
+    InitializeSignedCounter {
+        initial_value: i64,
+    },
+    IncrementSigned,
+    DecrementSigned,
+    /// Hands control of the counter to `new_authority`; the current authority must sign.
+    TransferAuthority {
+        new_authority: Pubkey,
+    },
+    /// Counterpart to `AddToCounter`; subtracts `amount`, failing with `Underflow` below 0.
+    DecrementBy {
+        amount: u64,
+    },
+    MultiplyCounter {
+        factor: u64,
+    },
+    /// Grows a legacy count-only account to the current layout, paid for by the payer.
+    /// The counter must sign.
+    MigrateCounter,
+    /// Sets the count to `new_value` only if it currently equals `expected`.
+    CompareAndSet {
+        expected: u64,
+        new_value: u64,
+    },
+    /// Read-only; returns the count as return data. Takes just the counter account.
+    GetCount,
+    /// Chooses whether `IncrementCounter` wraps around at `u64::MAX` instead of failing.
+    SetWrapping {
+        wrapping: bool,
+    },
+    /// Resets an existing counter in place to `initial_value`; cheaper than close + init.
+    Reinitialize {
+        initial_value: u64,
+    },
+    /// Moves the count to the nearest bound if it lies outside `low..=high`.
+    ClampCounter {
+        low: u64,
+        high: u64,
+    },
+    /// Applies `IncrementCounter` to each counter, which must all be distinct. Accounts: the
+    /// shared authority, then the counters.
+    BatchIncrement,
+    /// Same accounts as `InitializeCounter`; succeeds without changes if the counter exists.
+    InitializeIfNeeded {
+        initial_value: u64,
+    },
+    /// Increments when `up` is set, decrements otherwise.
+    Step {
+        up: bool,
+    },
+    /// Makes the counter permanently read-only; there is deliberately no thaw.
+    FreezeCounter,
+    /// `AddToCounter` that saturates at `u64::MAX` on overflow when `saturate` is set.
+    AddChecked {
+        amount: u64,
+        saturate: bool,
+    },
+    /// Read-only; returns the sum of every passed counter's count as return data.
+    SumCounters,
+    /// Initializes a counter the client already created with `CounterAccount::SIZE` bytes
+    /// and this program as owner. Accounts: the counter and its authority, both signing.
+    InitializeData {
+        initial_value: u64,
+    },
+    /// Creates the authority's one counter at `find_registry_address`. Accounts: the
+    /// registry counter, the authority paying for it, and the system program.
+    InitializeRegistryCounter {
+        initial_value: u64,
+    },
+    /// Subtracts `amount`, failing with `InsufficientBalance` if the count is below it.
+    DecrementExact {
+        amount: u64,
+    },
+    /// `GetCount` that encodes the returned count as big-endian when `big_endian` is set.
+    ReadCount {
+        big_endian: bool,
+    },
+    /// Makes `IncrementCounter` cycle back to 0 at `modulus`; 0 turns cycling off.
+    SetModulus {
+        modulus: u64,
+    },
+    /// Same accounts as `InitializeCounter`; runs its checks and returns the rent it would
+    /// cost as return data, without creating the counter.
+    SimulateInitialize {
+        initial_value: u64,
+    },
+    /// `InitializeCounter` without caps or name; when `require_authority` is false anyone
+    /// may change the count, otherwise only the payer, as with `InitializeCounter`.
+    InitializeCounterWithAccess {
+        initial_value: u64,
+        require_authority: bool,
+    },
+    /// Read-only; returns the full Borsh-encoded `CounterAccount` as return data.
+    GetMetadata,
+    /// Permanently drops the authority, after which anyone may change the count.
+    RenounceAuthority,
+    /// Adds the stored `step` multiplied by `weight`.
+    WeightedIncrement {
+        weight: u64,
+    },
+    /// Increments by 1 if at least `min_interval_secs` passed since `last_updated`.
+    IncrementIfElapsed {
+        min_interval_secs: i64,
+    },
+    /// Sets the count, step and max value in one go; `max_value` 0 means no cap.
+    Configure {
+        value: u64,
+        step: u64,
+        max_value: u64,
+    },
+    /// Read-only; returns `count - baseline`, saturating at 0, as return data.
+    CountSinceBaseline {
+        baseline: u64,
+    },
+    /// Increments by `step` if a second, read-only counter passed after the authority
+    /// holds at least `threshold`.
+    IncrementIfOther {
+        threshold: u64,
+    },
+    /// Decrements by 1 and closes the counter if it reached 0. Same accounts as
+    /// `CloseCounter`.
+    DecrementAndMaybeClose,
+    /// Read-only; fails with `AssertionFailed` unless the count equals `expected`.
+    AssertCount {
+        expected: u64,
+    },
+    /// `SetCounter` that only applies while the count still equals `old`.
+    SetChecked {
+        old: u64,
+        new: u64,
+    },
+    /// Subtracts `amount` from a source counter and adds it to a destination counter;
+    /// takes the source, the destination and the authority of both.
+    TransferCount {
+        amount: u64,
+    },
+    /// Creates the authority's counter at `find_indexed_counter` for `index`. Accounts: the
+    /// indexed counter, the authority paying for it, and the system program.
+    InitializeIndexedCounter {
+        initial_value: u64,
+        index: u64,
+    },
+    /// Proposes `new_authority`, which only takes over once it signs `AcceptAuthority`.
+    ProposeAuthority {
+        new_authority: Pubkey,
+    },
+    /// Signed by the pending authority in place of the current one; completes the handoff.
+    AcceptAuthority,
+    /// Drops a pending authority proposal.
+    CancelAuthorityProposal,
+}
+
+/// Wire format version written as the first byte of every instruction's data.
+pub const INSTRUCTION_VERSION: u8 = 1;
+
+/// Longest instruction data accepted; well above the largest valid instruction, an
+/// `InitializeCounter` carrying a `MAX_NAME_LEN` name.
+pub const MAX_INSTRUCTION_LEN: usize = 128;
+
+impl CounterInstruction {
+    /// Number of variants; Borsh tags at or above this are unknown instructions.
+    pub const VARIANT_COUNT: u8 = 55;
+
+    /// Encodes the instruction as `INSTRUCTION_VERSION` followed by its Borsh bytes.
+    pub fn pack(&self) -> Vec<u8> {
+        let mut data = vec![INSTRUCTION_VERSION];
+        // Writing into a `Vec` cannot fail.
+        self.serialize(&mut data).unwrap();
+        data
+    }
+
+    /// Decodes instruction data produced by `pack`, rejecting unknown versions.
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        let (&version, payload) = data.split_first().ok_or_else(|| {
+            msg!("Empty instruction data");
+            CounterError::EmptyInstructionData
+        })?;
+        if version != INSTRUCTION_VERSION {
+            msg!("Unsupported instruction version: {}", version);
+            return Err(CounterError::UnsupportedInstructionVersion.into());
+        }
+
+        Self::try_from_slice(payload).map_err(|_| {
+            match payload.first() {
+                None => msg!("Instruction tag is missing"),
+                Some(&tag) if tag >= Self::VARIANT_COUNT => {
+                    msg!("Unknown instruction tag: {}", tag)
+                }
+                Some(&tag) => msg!("Invalid data for instruction tag: {}", tag),
+            }
+            CounterError::InvalidInstructionData.into()
+        })
+    }
+}
+
+/// Program-specific failures, surfaced to clients as `ProgramError::Custom(code)`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CounterError {
+    Overflow = 0,
+    Underflow = 1,
+    AlreadyInitialized = 2,
+    Unauthorized = 3,
+    InvalidInstructionData = 4,
+    InvalidStep = 5,
+    CounterPaused = 6,
+    MaxValueExceeded = 7,
+    UnsupportedVersion = 8,
+    NotRentExempt = 9,
+    InvalidAccountSize = 10,
+    InvalidAuthority = 11,
+    MinValueViolated = 12,
+    CompareFailed = 13,
+    NameTooLong = 14,
+    InvalidRange = 15,
+    DuplicateAccount = 16,
+    UnsupportedInstructionVersion = 17,
+    CounterFrozen = 18,
+    EmptyInstructionData = 19,
+    InsufficientBalance = 20,
+    InstructionTooLong = 21,
+    LegacyAccountNeedsMigration = 22,
+    UninitializedAccount = 23,
+    TooSoon = 24,
+    ConditionNotMet = 25,
+    AddOverflow = 26,
+    MulOverflow = 27,
+    AssertionFailed = 28,
+    StaleValue = 29,
+}
+
+impl From<CounterError> for ProgramError {
+    fn from(e: CounterError) -> Self {
+        ProgramError::Custom(e as u32)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::result_large_err)]
+mod test {
+    use super::*;
+    use litesvm::{ LiteSVM, types::TransactionResult };
+    use solana_program::{ entrypoint::SUCCESS, program_stubs };
+    use solana_sdk::{
+        account::{ Account, ReadableAccount },
+        clock::Clock,
+        instruction::{ AccountMeta, Instruction, InstructionError },
+        message::Message,
+        signature::{ Keypair, Signer },
+        system_program,
+        transaction::{ Transaction, TransactionError },
+    };
+
+    fn setup() -> (LiteSVM, Keypair, Pubkey) {
+        let mut svm = LiteSVM::new();
+        let payer = Keypair::new();
+        let program_id = Keypair::new().pubkey();
+
+        svm.add_program_from_file(program_id, "target/deploy/counter_program.so").expect(
+            "Failed to load program"
+        );
+        svm.airdrop(&payer.pubkey(), 1_000_000_000).expect("Failed to airdrop");
+
+        (svm, payer, program_id)
+    }
+
+    fn initialize_counter(
+        svm: &mut LiteSVM,
+        program_id: Pubkey,
+        payer: &Keypair,
+        counter_keypair: &Keypair,
+        initial_value: u64
+    ) -> TransactionResult {
+        send_initialize_instruction(
+            svm,
+            program_id,
+            payer,
+            counter_keypair,
+            &(CounterInstruction::InitializeCounter {
+                initial_value,
+                max_value: 0,
+                min_value: 0,
+                name: String::new(),
+                on_overflow: OverflowPolicy::Error,
+            })
+        )
+    }
+
+    fn send_initialize_instruction(
+        svm: &mut LiteSVM,
+        program_id: Pubkey,
+        payer: &Keypair,
+        counter_keypair: &Keypair,
+        initialize_instruction: &CounterInstruction
+    ) -> TransactionResult {
+        let instruction = Instruction::new_with_bytes(
+            program_id,
+            &initialize_instruction.pack(),
+            vec![
+                AccountMeta::new(counter_keypair.pubkey(), true),
+                AccountMeta::new(payer.pubkey(), true),
+                AccountMeta::new_readonly(system_program::id(), false)
+            ]
+        );
+        send_instruction(svm, instruction, payer, &[counter_keypair])
+    }
+
+    fn send_counter_instruction(
+        svm: &mut LiteSVM,
+        program_id: Pubkey,
+        payer: &Keypair,
+        counter: &Pubkey,
+        counter_instruction: &CounterInstruction
+    ) -> TransactionResult {
+        send_counter_instruction_with_authority(
+            svm,
+            program_id,
+            payer,
+            counter,
+            payer,
+            counter_instruction
+        )
+    }
+
+    fn send_counter_instruction_with_authority(
+        svm: &mut LiteSVM,
+        program_id: Pubkey,
+        payer: &Keypair,
+        counter: &Pubkey,
+        authority: &Keypair,
+        counter_instruction: &CounterInstruction
+    ) -> TransactionResult {
+        let instruction = Instruction::new_with_bytes(
+            program_id,
+            &counter_instruction.pack(),
+            vec![
+                AccountMeta::new(*counter, false),
+                AccountMeta::new_readonly(authority.pubkey(), true)
+            ]
+        );
+        send_instruction(svm, instruction, payer, &[authority])
+    }
+
+    fn send_increment(
+        svm: &mut LiteSVM,
+        program_id: Pubkey,
+        counter: &Pubkey,
+        payer: &Keypair
+    ) -> TransactionResult {
+        let instruction = instruction::increment_counter(&program_id, counter, &payer.pubkey());
+        send_instruction(svm, instruction, payer, &[])
+    }
+
+    fn send_instruction(
+        svm: &mut LiteSVM,
+        instruction: Instruction,
+        payer: &Keypair,
+        signers: &[&Keypair]
+    ) -> TransactionResult {
+        let mut all_signers = vec![payer];
+        all_signers.extend_from_slice(signers);
+
+        let message = Message::new(&[instruction], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&all_signers, message, svm.latest_blockhash());
+        let result = svm.send_transaction(transaction);
+        // Identical transactions within one blockhash are rejected as duplicates.
+        svm.expire_blockhash();
+        result
+    }
+
+    fn get_counter(svm: &LiteSVM, counter: &Pubkey) -> CounterAccount {
+        let account = svm.get_account(counter).expect("Failed to get counter account");
+        CounterAccount::try_from_slice(account.data()).expect("Failed to deserialize counter data")
+    }
+
+    fn assert_instruction_error(result: TransactionResult, expected: InstructionError) {
+        let err = result.expect_err("Transaction should fail").err;
+        assert_eq!(err, TransactionError::InstructionError(0, expected));
+    }
+
+    #[test]
+    fn test_counter_program() {
+        let mut svm = LiteSVM::new();
+        let payer = Keypair::new();
+        let counter_keypair = Keypair::new();
+        let initial_value: u64 = 42;
+
+        let program_keypair = Keypair::new();
+        let program_id = program_keypair.pubkey();
+
+        svm.add_program_from_file(program_id, "target/deploy/counter_program.so").expect(
+            "Failed to load program"
+        );
+
+        svm.airdrop(&payer.pubkey(), 1_000_000_000).expect("Failed to airdrop");
+
+        println!("Testing counter initialization");
+        // msg!("Test message");
+
+        let init_instruction_data = (CounterInstruction::InitializeCounter {
+            initial_value,
+            max_value: 0,
+            min_value: 0,
+            name: String::new(),
+            on_overflow: OverflowPolicy::Error,
+        }).pack();
+        let initialize_instruction = Instruction::new_with_bytes(
+            program_id,
+            &init_instruction_data,
+            vec![
+                AccountMeta::new(counter_keypair.pubkey(), true),
+                AccountMeta::new(payer.pubkey(), true),
+                AccountMeta::new_readonly(system_program::id(), false)
+            ]
+        );
+
+        let message = Message::new(&[initialize_instruction], Some(&payer.pubkey()));
+        let transaction = Transaction::new(
+            &[&payer, &counter_keypair],
+            message,
+            svm.latest_blockhash()
+        );
+        let result = svm.send_transaction(transaction);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let logs = result.unwrap().logs;
         println!("Transaction logs:\n{:#?}", logs);
         //
 
-        let account = svm
-            .get_account(&counter_keypair.pubkey())
-            .expect("Failed to get counter account");
-        let counter = CounterAccount::try_from_slice(account.data()).expect(
-            "Failed to deserialize counter data"
+        let account = svm
+            .get_account(&counter_keypair.pubkey())
+            .expect("Failed to get counter account");
+        let counter = CounterAccount::try_from_slice(account.data()).expect(
+            "Failed to deserialize counter data"
+        );
+
+        assert_eq!(counter.count, 42);
+        println!("Counter initialized successfully with value: {}", counter.count);
+
+        println!("Testing counter incremenet...");
+        let result = send_increment(&mut svm, program_id, &counter_keypair.pubkey(), &payer);
+        assert!(result.is_ok(), "Increment transaction should succeed");
+        assert_eq!(get_counter(&svm, &counter_keypair.pubkey()).count, 43);
+
+        let logs = result.unwrap().logs;
+        println!("Transaction logs:\n{:#?}", logs);
+    }
+
+    #[test]
+    fn test_repeated_increments_in_one_transaction() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 42);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let increment = instruction::increment_counter(&program_id, &counter, &payer.pubkey());
+        let message = Message::new(
+            &[increment.clone(), increment.clone(), increment],
+            Some(&payer.pubkey())
+        );
+        let transaction = Transaction::new(&[&payer], message, svm.latest_blockhash());
+        let result = svm.send_transaction(transaction);
+        assert!(result.is_ok(), "Repeated increments should succeed: {:?}", result.err());
+
+        let counter_data = get_counter(&svm, &counter);
+        assert_eq!(counter_data.count, 45);
+        assert_eq!(counter_data.update_count, 3);
+    }
+
+    #[test]
+    fn test_decrement_counter() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 1);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter_keypair.pubkey(),
+            &CounterInstruction::DecrementCounter
+        );
+        assert!(result.is_ok(), "Decrement transaction should succeed");
+        assert_eq!(get_counter(&svm, &counter_keypair.pubkey()).count, 0);
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter_keypair.pubkey(),
+            &CounterInstruction::DecrementCounter
+        );
+        assert_instruction_error(result, InstructionError::Custom(CounterError::Underflow as u32));
+        assert_eq!(get_counter(&svm, &counter_keypair.pubkey()).count, 0);
+    }
+
+    #[test]
+    fn test_reset_counter() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 5);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        for _ in 0..3 {
+            let result = send_counter_instruction(
+                &mut svm,
+                program_id,
+                &payer,
+                &counter_keypair.pubkey(),
+                &CounterInstruction::IncrementCounter
+            );
+            assert!(result.is_ok(), "Increment transaction should succeed");
+        }
+        assert_eq!(get_counter(&svm, &counter_keypair.pubkey()).count, 8);
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter_keypair.pubkey(),
+            &CounterInstruction::ResetCounter
+        );
+        assert!(result.is_ok(), "Reset transaction should succeed");
+        assert_eq!(get_counter(&svm, &counter_keypair.pubkey()).count, 0);
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter_keypair.pubkey(),
+            &CounterInstruction::ResetCounter
+        );
+        assert!(result.is_ok(), "Resetting a zeroed counter should succeed");
+        assert_eq!(get_counter(&svm, &counter_keypair.pubkey()).count, 0);
+    }
+
+    #[test]
+    fn test_set_counter() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 42);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter_keypair.pubkey(),
+            &(CounterInstruction::SetCounter { value: 1000 })
+        );
+        assert!(result.is_ok(), "Set transaction should succeed");
+        assert_eq!(get_counter(&svm, &counter_keypair.pubkey()).count, 1000);
+    }
+
+    #[test]
+    fn test_add_to_counter() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 42);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter_keypair.pubkey(),
+            &(CounterInstruction::AddToCounter { amount: 100 })
+        );
+        assert!(result.is_ok(), "Add transaction should succeed");
+        assert_eq!(get_counter(&svm, &counter_keypair.pubkey()).count, 142);
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter_keypair.pubkey(),
+            &(CounterInstruction::AddToCounter { amount: u64::MAX })
+        );
+        assert_instruction_error(
+            result,
+            InstructionError::Custom(CounterError::AddOverflow as u32)
+        );
+        assert_eq!(get_counter(&svm, &counter_keypair.pubkey()).count, 142);
+    }
+
+    #[test]
+    fn test_subtract_from_counter() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 42);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter_keypair.pubkey(),
+            &(CounterInstruction::SubtractFromCounter { amount: 40 })
+        );
+        assert!(result.is_ok(), "Subtract transaction should succeed");
+        assert_eq!(get_counter(&svm, &counter_keypair.pubkey()).count, 2);
+    }
+
+    #[test]
+    fn test_subtract_more_than_count_fails() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 42);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter_keypair.pubkey(),
+            &(CounterInstruction::SubtractFromCounter { amount: 43 })
+        );
+        assert_instruction_error(result, InstructionError::Custom(CounterError::Underflow as u32));
+        assert_eq!(get_counter(&svm, &counter_keypair.pubkey()).count, 42);
+    }
+
+    #[test]
+    fn test_double_initialize_fails() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 42);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 7);
+        assert_instruction_error(result, InstructionError::Custom(CounterError::AlreadyInitialized as u32));
+        assert_eq!(get_counter(&svm, &counter_keypair.pubkey()).count, 42);
+    }
+
+    #[test]
+    fn test_initialize_requires_signers() {
+        let (mut svm, fee_payer, program_id) = setup();
+        let payer = Keypair::new();
+        let counter_keypair = Keypair::new();
+        svm.airdrop(&payer.pubkey(), 1_000_000_000).expect("Failed to airdrop");
+
+        let instruction = Instruction::new_with_bytes(
+            program_id,
+            &(CounterInstruction::InitializeCounter {
+                initial_value: 42,
+                max_value: 0,
+                min_value: 0,
+                name: String::new(),
+                on_overflow: OverflowPolicy::Error,
+            }).pack(),
+            vec![
+                AccountMeta::new(counter_keypair.pubkey(), true),
+                AccountMeta::new(payer.pubkey(), false),
+                AccountMeta::new_readonly(system_program::id(), false)
+            ]
+        );
+        let result = send_instruction(&mut svm, instruction, &fee_payer, &[&counter_keypair]);
+        assert_instruction_error(result, InstructionError::MissingRequiredSignature);
+
+        let instruction = Instruction::new_with_bytes(
+            program_id,
+            &(CounterInstruction::InitializeCounter {
+                initial_value: 42,
+                max_value: 0,
+                min_value: 0,
+                name: String::new(),
+                on_overflow: OverflowPolicy::Error,
+            }).pack(),
+            vec![
+                AccountMeta::new(counter_keypair.pubkey(), false),
+                AccountMeta::new(payer.pubkey(), true),
+                AccountMeta::new_readonly(system_program::id(), false)
+            ]
+        );
+        let result = send_instruction(&mut svm, instruction, &fee_payer, &[&payer]);
+        // A non-signing counter is treated as a PDA, and a random key fails the seed check.
+        assert_instruction_error(result, InstructionError::InvalidSeeds);
+
+        assert!(svm.get_account(&counter_keypair.pubkey()).is_none());
+    }
+
+    #[test]
+    fn test_initialize_rejects_wrong_system_program() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+
+        let instruction = Instruction::new_with_bytes(
+            program_id,
+            &(CounterInstruction::InitializeCounter {
+                initial_value: 42,
+                max_value: 0,
+                min_value: 0,
+                name: String::new(),
+                on_overflow: OverflowPolicy::Error,
+            }).pack(),
+            vec![
+                AccountMeta::new(counter_keypair.pubkey(), true),
+                AccountMeta::new(payer.pubkey(), true),
+                AccountMeta::new_readonly(Pubkey::new_unique(), false)
+            ]
+        );
+        let result = send_instruction(&mut svm, instruction, &payer, &[&counter_keypair]);
+        assert_instruction_error(result, InstructionError::IncorrectProgramId);
+    }
+
+    #[test]
+    fn test_increment_overflow_returns_custom_error() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, u64::MAX);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter_keypair.pubkey(),
+            &CounterInstruction::IncrementCounter
+        );
+        assert_instruction_error(result, InstructionError::Custom(0));
+        assert_eq!(get_counter(&svm, &counter_keypair.pubkey()).count, u64::MAX);
+    }
+
+    #[test]
+    fn test_invalid_instruction_data_returns_custom_error() {
+        let (mut svm, payer, program_id) = setup();
+
+        let instruction = Instruction::new_with_bytes(
+            program_id,
+            &[INSTRUCTION_VERSION, 0xff],
+            vec![]
+        );
+        let result = send_instruction(&mut svm, instruction, &payer, &[]);
+        assert_instruction_error(
+            result,
+            InstructionError::Custom(CounterError::InvalidInstructionData as u32)
+        );
+    }
+
+    #[test]
+    fn test_empty_instruction_data_fails() {
+        let (mut svm, payer, program_id) = setup();
+
+        let instruction = Instruction::new_with_bytes(program_id, &[], vec![]);
+        let result = send_instruction(&mut svm, instruction, &payer, &[]);
+        let logs = result.as_ref().unwrap_err().meta.logs.clone();
+        assert_instruction_error(
+            result,
+            InstructionError::Custom(CounterError::EmptyInstructionData as u32)
+        );
+        assert!(logs.iter().any(|log| log.contains("Empty instruction data")), "{:#?}", logs);
+    }
+
+    #[test]
+    fn test_oversized_instruction_data_fails() {
+        let (mut svm, payer, program_id) = setup();
+
+        let mut data = CounterInstruction::IncrementCounter.pack();
+        data.resize(MAX_INSTRUCTION_LEN + 1, 0);
+        let instruction = Instruction::new_with_bytes(program_id, &data, vec![]);
+        let result = send_instruction(&mut svm, instruction, &payer, &[]);
+        let logs = result.as_ref().unwrap_err().meta.logs.clone();
+        assert_instruction_error(
+            result,
+            InstructionError::Custom(CounterError::InstructionTooLong as u32)
+        );
+        let message = format!("Instruction data is 129 bytes, limit is {}", MAX_INSTRUCTION_LEN);
+        assert!(logs.iter().any(|log| log.contains(&message)), "{:#?}", logs);
+    }
+
+    #[test]
+    fn test_unknown_instruction_tag_is_logged() {
+        let (mut svm, payer, program_id) = setup();
+
+        let last_tag = CounterInstruction::CancelAuthorityProposal.pack()[1];
+        assert_eq!(last_tag, CounterInstruction::VARIANT_COUNT - 1);
+
+        let instruction = Instruction::new_with_bytes(
+            program_id,
+            &[INSTRUCTION_VERSION, CounterInstruction::VARIANT_COUNT],
+            vec![]
+        );
+        let result = send_instruction(&mut svm, instruction, &payer, &[]);
+        let logs = result.as_ref().unwrap_err().meta.logs.clone();
+        assert_instruction_error(
+            result,
+            InstructionError::Custom(CounterError::InvalidInstructionData as u32)
+        );
+        let expected = format!("Unknown instruction tag: {}", CounterInstruction::VARIANT_COUNT);
+        assert!(logs.iter().any(|log| log.contains(&expected)), "Missing tag in {:#?}", logs);
+    }
+
+    #[test]
+    fn test_unknown_instruction_version_is_rejected() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 42);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let mut instruction = instruction::increment_counter(&program_id, &counter, &payer.pubkey());
+        assert_eq!(instruction.data[0], INSTRUCTION_VERSION);
+        instruction.data[0] = INSTRUCTION_VERSION + 1;
+        let result = send_instruction(&mut svm, instruction, &payer, &[]);
+        assert_instruction_error(
+            result,
+            InstructionError::Custom(CounterError::UnsupportedInstructionVersion as u32)
+        );
+        assert_eq!(get_counter(&svm, &counter).count, 42);
+    }
+
+    #[test]
+    fn test_authority_is_set_on_initialize() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 42);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+        assert_eq!(get_counter(&svm, &counter_keypair.pubkey()).authority, payer.pubkey());
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter_keypair.pubkey(),
+            &CounterInstruction::IncrementCounter
+        );
+        assert!(result.is_ok(), "Authorized increment should succeed");
+        assert_eq!(get_counter(&svm, &counter_keypair.pubkey()).count, 43);
+    }
+
+    #[test]
+    fn test_unauthorized_increment_fails() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let intruder = Keypair::new();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 42);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let result = send_counter_instruction_with_authority(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter_keypair.pubkey(),
+            &intruder,
+            &CounterInstruction::IncrementCounter
+        );
+        assert_instruction_error(result, InstructionError::Custom(CounterError::Unauthorized as u32));
+
+        let instruction = Instruction::new_with_bytes(
+            program_id,
+            &CounterInstruction::IncrementCounter.pack(),
+            vec![
+                AccountMeta::new(counter_keypair.pubkey(), false),
+                AccountMeta::new_readonly(payer.pubkey(), false)
+            ]
+        );
+        svm.airdrop(&intruder.pubkey(), 1_000_000_000).expect("Failed to airdrop");
+        let result = send_instruction(&mut svm, instruction, &intruder, &[]);
+        assert_instruction_error(result, InstructionError::MissingRequiredSignature);
+
+        assert_eq!(get_counter(&svm, &counter_keypair.pubkey()).count, 42);
+    }
+
+    #[test]
+    fn test_close_counter() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let recipient = Pubkey::new_unique();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 42);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+        let counter_lamports = svm.get_balance(&counter_keypair.pubkey()).unwrap();
+
+        let instruction = Instruction::new_with_bytes(
+            program_id,
+            &CounterInstruction::CloseCounter.pack(),
+            vec![
+                AccountMeta::new(counter_keypair.pubkey(), false),
+                AccountMeta::new_readonly(payer.pubkey(), true),
+                AccountMeta::new(recipient, false)
+            ]
+        );
+        let result = send_instruction(&mut svm, instruction, &payer, &[]);
+        assert!(result.is_ok(), "Close transaction should succeed");
+
+        assert_eq!(svm.get_balance(&recipient).unwrap(), counter_lamports);
+        let closed = svm.get_account(&counter_keypair.pubkey());
+        assert!(
+            closed.is_none_or(|account| account.lamports == 0 && account.data.is_empty()),
+            "Counter account should be emptied"
+        );
+    }
+
+    #[test]
+    fn test_close_counter_defaults_to_authority() {
+        let (mut svm, payer, program_id) = setup();
+        let fee_payer = Keypair::new();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+        svm.airdrop(&fee_payer.pubkey(), 1_000_000_000).expect("Failed to airdrop");
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 42);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+        let counter_lamports = svm.get_balance(&counter).unwrap();
+        let authority_lamports = svm.get_balance(&payer.pubkey()).unwrap();
+
+        let result = send_instruction(
+            &mut svm,
+            instruction::close_counter_to_authority(&program_id, &counter, &payer.pubkey()),
+            &fee_payer,
+            &[&payer]
+        );
+        assert!(result.is_ok(), "Close transaction should succeed");
+
+        let authority_balance = svm.get_balance(&payer.pubkey()).unwrap();
+        assert_eq!(authority_balance, authority_lamports + counter_lamports);
+        assert_eq!(svm.get_balance(&counter).unwrap_or(0), 0);
+    }
+
+    #[test]
+    fn test_decrement_and_maybe_close() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+        let recipient = Pubkey::new_unique();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 2);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+        let counter_lamports = svm.get_balance(&counter).unwrap();
+
+        let authority = payer.pubkey();
+        let instruction = || {
+            instruction::decrement_and_maybe_close(&program_id, &counter, &authority, &recipient)
+        };
+        let result = send_instruction(&mut svm, instruction(), &payer, &[]);
+        assert!(result.is_ok(), "Decrement to 1 should succeed");
+        assert_eq!(get_counter(&svm, &counter).count, 1);
+        assert_eq!(svm.get_balance(&recipient).unwrap_or(0), 0);
+
+        let result = send_instruction(&mut svm, instruction(), &payer, &[]);
+        assert!(result.is_ok(), "Decrement to 0 should succeed and close");
+        assert_eq!(svm.get_balance(&recipient).unwrap(), counter_lamports);
+        let closed = svm.get_account(&counter);
+        assert!(
+            closed.is_none_or(|account| account.lamports == 0 && account.data.is_empty()),
+            "Counter account should be emptied"
+        );
+    }
+
+    #[test]
+    fn test_decrement_and_maybe_close_from_zero_fails() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+        let recipient = Pubkey::new_unique();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 0);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let instruction = instruction::decrement_and_maybe_close(
+            &program_id,
+            &counter,
+            &payer.pubkey(),
+            &recipient
+        );
+        let result = send_instruction(&mut svm, instruction, &payer, &[]);
+        assert_instruction_error(result, InstructionError::Custom(CounterError::Underflow as u32));
+        assert_eq!(get_counter(&svm, &counter).count, 0);
+    }
+
+    #[test]
+    fn test_close_foreign_account_fails() {
+        let (mut svm, payer, program_id) = setup();
+        let victim = Pubkey::new_unique();
+        let recipient = Pubkey::new_unique();
+
+        // Looks like a counter controlled by `payer`, but is owned by the system program.
+        let forged = CounterAccount { authority: payer.pubkey(), ..CounterAccount::new(42) };
+        let victim_account = Account {
+            lamports: 5_000_000,
+            data: borsh::to_vec(&forged).unwrap(),
+            owner: system_program::id(),
+            executable: false,
+            rent_epoch: 0,
+        };
+        svm.set_account(victim, victim_account).unwrap();
+
+        let result = send_instruction(
+            &mut svm,
+            instruction::close_counter(&program_id, &victim, &payer.pubkey(), &recipient),
+            &payer,
+            &[]
+        );
+        assert_instruction_error(result, InstructionError::IncorrectProgramId);
+        assert_eq!(svm.get_balance(&victim).unwrap(), 5_000_000);
+        assert!(svm.get_balance(&recipient).is_none_or(|lamports| lamports == 0));
+    }
+
+    #[test]
+    fn test_payer_as_counter_fails() {
+        let (mut svm, payer, program_id) = setup();
+
+        let instruction = instruction::initialize_counter(
+            &program_id,
+            &payer.pubkey(),
+            &payer.pubkey(),
+            42
+        );
+        let result = send_instruction(&mut svm, instruction, &payer, &[]);
+        assert_instruction_error(
+            result,
+            InstructionError::Custom(CounterError::DuplicateAccount as u32)
+        );
+        assert_eq!(svm.get_account(&payer.pubkey()).unwrap().owner, system_program::id());
+    }
+
+    #[test]
+    fn test_u128_counter_passes_u64_max() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let initial_value = (u64::MAX as u128) - 1;
+
+        let instruction = Instruction::new_with_bytes(
+            program_id,
+            &(CounterInstruction::InitializeCounterU128 { initial_value }).pack(),
+            vec![
+                AccountMeta::new(counter_keypair.pubkey(), true),
+                AccountMeta::new(payer.pubkey(), true),
+                AccountMeta::new_readonly(system_program::id(), false)
+            ]
+        );
+        let result = send_instruction(&mut svm, instruction, &payer, &[&counter_keypair]);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter_keypair.pubkey(),
+            &CounterInstruction::IncrementCounterU128
+        );
+        assert!(result.is_ok(), "Increment transaction should succeed");
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter_keypair.pubkey(),
+            &(CounterInstruction::AddToCounterU128 { amount: 10 })
+        );
+        assert!(result.is_ok(), "Add transaction should succeed");
+
+        let account = svm.get_account(&counter_keypair.pubkey()).unwrap();
+        let counter = CounterAccountU128::try_from_slice(account.data()).unwrap();
+        assert_eq!(counter.count, (u64::MAX as u128) + 10);
+    }
+
+    #[test]
+    fn test_increment_if_elapsed() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let mut clock: Clock = svm.get_sysvar();
+        clock.unix_timestamp = 1_700_000_000;
+        svm.set_sysvar(&clock);
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 42);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        clock.unix_timestamp += 30;
+        svm.set_sysvar(&clock);
+
+        let cooldown = CounterInstruction::IncrementIfElapsed { min_interval_secs: 60 };
+        let result = send_counter_instruction(&mut svm, program_id, &payer, &counter, &cooldown);
+        assert_instruction_error(result, InstructionError::Custom(CounterError::TooSoon as u32));
+        assert_eq!(get_counter(&svm, &counter).count, 42);
+
+        clock.unix_timestamp += 30;
+        svm.set_sysvar(&clock);
+
+        let result = send_counter_instruction(&mut svm, program_id, &payer, &counter, &cooldown);
+        assert!(result.is_ok(), "Increment after the interval should succeed");
+        let counter_data = get_counter(&svm, &counter);
+        assert_eq!(counter_data.count, 43);
+        assert_eq!(counter_data.last_updated, 1_700_000_060);
+    }
+
+    #[test]
+    fn test_last_updated_tracks_clock() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+
+        let mut clock: Clock = svm.get_sysvar();
+        clock.unix_timestamp = 1_700_000_000;
+        svm.set_sysvar(&clock);
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 42);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+        assert_eq!(get_counter(&svm, &counter_keypair.pubkey()).last_updated, 1_700_000_000);
+
+        clock.unix_timestamp += 60;
+        svm.set_sysvar(&clock);
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter_keypair.pubkey(),
+            &CounterInstruction::IncrementCounter
+        );
+        assert!(result.is_ok(), "Increment transaction should succeed");
+
+        let counter = get_counter(&svm, &counter_keypair.pubkey());
+        assert_eq!(counter.count, 43);
+        assert_eq!(counter.last_updated, 1_700_000_060);
+
+        let logs = result.unwrap().logs;
+        assert!(logs.iter().any(|log| log.contains("Counter incremented to: 43 at 1700000060")));
+    }
+
+    #[test]
+    fn test_update_count_tracks_mutations() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 42);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+        assert_eq!(get_counter(&svm, &counter_keypair.pubkey()).update_count, 0);
+
+        let mutations = [
+            CounterInstruction::IncrementCounter,
+            CounterInstruction::DecrementCounter,
+            CounterInstruction::SetCounter { value: 1000 },
+            CounterInstruction::AddToCounter { amount: 5 },
+            CounterInstruction::SubtractFromCounter { amount: 5 },
+            CounterInstruction::ResetCounter,
+        ];
+        for mutation in &mutations {
+            let result = send_counter_instruction(
+                &mut svm,
+                program_id,
+                &payer,
+                &counter_keypair.pubkey(),
+                mutation
+            );
+            assert!(result.is_ok(), "{:?} should succeed", mutation);
+        }
+
+        // Failed mutations are not counted.
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter_keypair.pubkey(),
+            &CounterInstruction::DecrementCounter
+        );
+        assert!(result.is_err(), "Decrement below zero should fail");
+
+        let counter = get_counter(&svm, &counter_keypair.pubkey());
+        assert_eq!(counter.update_count, mutations.len() as u64);
+    }
+
+    #[test]
+    fn test_increment_uses_step() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 42);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+        assert_eq!(get_counter(&svm, &counter_keypair.pubkey()).step, 1);
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter_keypair.pubkey(),
+            &(CounterInstruction::SetStep { step: 5 })
+        );
+        assert!(result.is_ok(), "SetStep transaction should succeed");
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter_keypair.pubkey(),
+            &CounterInstruction::IncrementCounter
+        );
+        assert!(result.is_ok(), "Increment transaction should succeed");
+
+        let counter = get_counter(&svm, &counter_keypair.pubkey());
+        assert_eq!(counter.step, 5);
+        assert_eq!(counter.count, 47);
+    }
+
+    #[test]
+    fn test_set_step_rejects_zero() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 42);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter_keypair.pubkey(),
+            &(CounterInstruction::SetStep { step: 0 })
+        );
+        assert_instruction_error(result, InstructionError::Custom(CounterError::InvalidStep as u32));
+        assert_eq!(get_counter(&svm, &counter_keypair.pubkey()).step, 1);
+    }
+
+    #[test]
+    fn test_pause_and_resume_counter() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 42);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter_keypair.pubkey(),
+            &CounterInstruction::PauseCounter
+        );
+        assert!(result.is_ok(), "Pause transaction should succeed");
+        assert!(get_counter(&svm, &counter_keypair.pubkey()).is_paused);
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter_keypair.pubkey(),
+            &CounterInstruction::IncrementCounter
+        );
+        assert_instruction_error(result, InstructionError::Custom(CounterError::CounterPaused as u32));
+        assert_eq!(get_counter(&svm, &counter_keypair.pubkey()).count, 42);
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter_keypair.pubkey(),
+            &CounterInstruction::ResumeCounter
+        );
+        assert!(result.is_ok(), "Resume transaction should succeed");
+        assert!(!get_counter(&svm, &counter_keypair.pubkey()).is_paused);
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter_keypair.pubkey(),
+            &CounterInstruction::IncrementCounter
+        );
+        assert!(result.is_ok(), "Increment after resume should succeed");
+        assert_eq!(get_counter(&svm, &counter_keypair.pubkey()).count, 43);
+    }
+
+    #[test]
+    fn test_max_value_cap() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+
+        let result = send_initialize_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter_keypair,
+            &(CounterInstruction::InitializeCounter {
+                initial_value: 40,
+                max_value: 50,
+                min_value: 0,
+                name: String::new(),
+                on_overflow: OverflowPolicy::Error,
+            })
+        );
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter_keypair.pubkey(),
+            &(CounterInstruction::AddToCounter { amount: 9 })
+        );
+        assert!(result.is_ok(), "Add below the cap should succeed");
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter_keypair.pubkey(),
+            &CounterInstruction::IncrementCounter
+        );
+        assert!(result.is_ok(), "Increment up to the cap should succeed");
+        assert_eq!(get_counter(&svm, &counter_keypair.pubkey()).count, 50);
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter_keypair.pubkey(),
+            &CounterInstruction::IncrementCounter
+        );
+        assert_instruction_error(
+            result,
+            InstructionError::Custom(CounterError::MaxValueExceeded as u32)
+        );
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter_keypair.pubkey(),
+            &(CounterInstruction::AddToCounter { amount: 1 })
+        );
+        assert_instruction_error(
+            result,
+            InstructionError::Custom(CounterError::MaxValueExceeded as u32)
+        );
+        assert_eq!(get_counter(&svm, &counter_keypair.pubkey()).count, 50);
+    }
+
+    #[test]
+    fn test_unknown_version_is_rejected() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 42);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+        assert_eq!(get_counter(&svm, &counter_keypair.pubkey()).version, CURRENT_VERSION);
+
+        let mut account = svm.get_account(&counter_keypair.pubkey()).unwrap();
+        account.data[0] = CURRENT_VERSION + 1;
+        svm.set_account(counter_keypair.pubkey(), account).unwrap();
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter_keypair.pubkey(),
+            &CounterInstruction::IncrementCounter
+        );
+        assert_instruction_error(
+            result,
+            InstructionError::Custom(CounterError::UnsupportedVersion as u32)
+        );
+    }
+
+    #[test]
+    fn test_pda_counter() {
+        let (mut svm, payer, program_id) = setup();
+        let (counter, bump) = find_counter_address(&payer.pubkey(), &program_id);
+
+        let instruction = Instruction::new_with_bytes(
+            program_id,
+            &(CounterInstruction::InitializeCounter {
+                initial_value: 42,
+                max_value: 0,
+                min_value: 0,
+                name: String::new(),
+                on_overflow: OverflowPolicy::Error,
+            }).pack(),
+            vec![
+                AccountMeta::new(counter, false),
+                AccountMeta::new(payer.pubkey(), true),
+                AccountMeta::new_readonly(system_program::id(), false)
+            ]
+        );
+        let result = send_instruction(&mut svm, instruction, &payer, &[]);
+        assert!(result.is_ok(), "PDA initialize transaction should succeed");
+
+        let account = svm.get_account(&counter).expect("Failed to get counter account");
+        assert_eq!(account.owner, program_id);
+        assert_eq!(get_counter(&svm, &counter).bump, bump);
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &CounterInstruction::IncrementCounter
+        );
+        assert!(result.is_ok(), "PDA increment transaction should succeed");
+        assert_eq!(get_counter(&svm, &counter).count, 43);
+    }
+
+    #[test]
+    fn test_registry_counter_is_unique_per_authority() {
+        let (mut svm, payer, program_id) = setup();
+        let (counter, bump) = find_registry_address(&payer.pubkey(), &program_id);
+
+        let initialize = instruction::initialize_registry_counter(&program_id, &payer.pubkey(), 5);
+        let result = send_instruction(&mut svm, initialize.clone(), &payer, &[]);
+        assert!(result.is_ok(), "Registry initialize transaction should succeed");
+        assert_eq!(get_counter(&svm, &counter).bump, bump);
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &CounterInstruction::IncrementCounter
+        );
+        assert!(result.is_ok(), "Registry increment transaction should succeed");
+        assert_eq!(get_counter(&svm, &counter).count, 6);
+
+        let result = send_instruction(&mut svm, initialize, &payer, &[]);
+        assert_instruction_error(result, InstructionError::Custom(CounterError::AlreadyInitialized as u32));
+        assert_eq!(get_counter(&svm, &counter).count, 6);
+    }
+
+    #[test]
+    fn test_indexed_counters() {
+        let (mut svm, payer, program_id) = setup();
+        let (first, _) = find_indexed_counter(&payer.pubkey(), 0, &program_id);
+        let (second, bump) = find_indexed_counter(&payer.pubkey(), 1, &program_id);
+        assert_ne!(first, second);
+
+        for (index, initial_value) in [(0u64, 5u64), (1, 7)] {
+            let initialize = instruction::initialize_indexed_counter(
+                &program_id,
+                &payer.pubkey(),
+                index,
+                initial_value
+            );
+            let result = send_instruction(&mut svm, initialize, &payer, &[]);
+            assert!(result.is_ok(), "Indexed initialize {} should succeed", index);
+        }
+        assert_eq!(get_counter(&svm, &first).count, 5);
+        let counter_data = get_counter(&svm, &second);
+        assert_eq!(counter_data.count, 7);
+        assert_eq!(counter_data.bump, bump);
+        assert_eq!(counter_data.authority, payer.pubkey());
+
+        // The index in the instruction has to match the address passed in.
+        let mut initialize = instruction::initialize_indexed_counter(
+            &program_id,
+            &payer.pubkey(),
+            2,
+            0
+        );
+        initialize.accounts[0].pubkey = first;
+        let result = send_instruction(&mut svm, initialize, &payer, &[]);
+        assert_instruction_error(result, InstructionError::InvalidSeeds);
+    }
+
+    #[test]
+    fn test_non_signer_counter_must_be_pda() {
+        let (mut svm, payer, program_id) = setup();
+        let spoofed = Pubkey::new_unique();
+
+        let instruction = Instruction::new_with_bytes(
+            program_id,
+            &(CounterInstruction::InitializeCounter {
+                initial_value: 42,
+                max_value: 0,
+                min_value: 0,
+                name: String::new(),
+                on_overflow: OverflowPolicy::Error,
+            }).pack(),
+            vec![
+                AccountMeta::new(spoofed, false),
+                AccountMeta::new(payer.pubkey(), true),
+                AccountMeta::new_readonly(system_program::id(), false)
+            ]
+        );
+        let result = send_instruction(&mut svm, instruction, &payer, &[]);
+        let logs = result.as_ref().unwrap_err().meta.logs.clone();
+        assert_instruction_error(result, InstructionError::InvalidSeeds);
+
+        let (expected, _) = find_counter_address(&payer.pubkey(), &program_id);
+        let message = format!("Invalid counter PDA: expected {}, got {}", expected, spoofed);
+        assert!(logs.iter().any(|log| log.contains(&message)), "{:#?}", logs);
+    }
+
+    #[test]
+    fn test_instruction_builders() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+        let recipient = Pubkey::new_unique();
+
+        let ix = instruction::initialize_counter(&program_id, &counter, &payer.pubkey(), 42);
+        let result = send_instruction(&mut svm, ix, &payer, &[&counter_keypair]);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let steps = [
+            instruction::increment_counter(&program_id, &counter, &payer.pubkey()),
+            instruction::add_to_counter(&program_id, &counter, &payer.pubkey(), 10),
+            instruction::subtract_from_counter(&program_id, &counter, &payer.pubkey(), 3),
+            instruction::decrement_counter(&program_id, &counter, &payer.pubkey()),
+        ];
+        for ix in steps {
+            let result = send_instruction(&mut svm, ix, &payer, &[]);
+            assert!(result.is_ok(), "Builder instruction should succeed");
+        }
+        assert_eq!(get_counter(&svm, &counter).count, 49);
+
+        let ix = instruction::close_counter(&program_id, &counter, &payer.pubkey(), &recipient);
+        let result = send_instruction(&mut svm, ix, &payer, &[]);
+        assert!(result.is_ok(), "Close transaction should succeed");
+        assert!(svm.get_balance(&recipient).unwrap_or(0) > 0);
+
+        let ix = instruction::initialize_pda_counter(&program_id, &payer.pubkey(), 7, 0);
+        let result = send_instruction(&mut svm, ix, &payer, &[]);
+        assert!(result.is_ok(), "PDA initialize transaction should succeed");
+        let (pda, _) = find_counter_address(&payer.pubkey(), &program_id);
+        assert_eq!(get_counter(&svm, &pda).count, 7);
+    }
+
+    #[test]
+    fn test_mutations_emit_events() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 42);
+        let logs = result.expect("Initialize transaction should succeed").logs;
+        let expected = CounterEvent::Initialized { counter, value: 42 };
+        assert!(logs.contains(&program_data_log(&expected)), "Missing event in {:#?}", logs);
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &(CounterInstruction::AddToCounter { amount: 8 })
+        );
+        let logs = result.expect("Add transaction should succeed").logs;
+        let expected = CounterEvent::Updated {
+            counter,
+            operation: CounterOperation::Add,
+            old_value: 42,
+            new_value: 50,
+        };
+        assert!(logs.contains(&program_data_log(&expected)), "Missing event in {:#?}", logs);
+    }
+
+    #[test]
+    fn test_wide_signed_and_close_emit_events() {
+        let (mut svm, payer, program_id) = setup();
+        let wide_keypair = Keypair::new();
+        let signed_keypair = Keypair::new();
+        let wide = wide_keypair.pubkey();
+        let signed = signed_keypair.pubkey();
+
+        let result = send_initialize_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &wide_keypair,
+            &(CounterInstruction::InitializeCounterU128 { initial_value: u64::MAX as u128 })
+        );
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &wide,
+            &(CounterInstruction::AddToCounterU128 { amount: 10 })
+        );
+        let logs = result.expect("Add transaction should succeed").logs;
+        let expected = CounterEvent::UpdatedU128 {
+            counter: wide,
+            operation: CounterOperation::Add,
+            old_value: u64::MAX as u128,
+            new_value: (u64::MAX as u128) + 10,
+        };
+        assert!(logs.contains(&program_data_log(&expected)), "Missing event in {:#?}", logs);
+
+        let result = send_initialize_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &signed_keypair,
+            &(CounterInstruction::InitializeSignedCounter { initial_value: 0 })
+        );
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &signed,
+            &CounterInstruction::DecrementSigned
+        );
+        let logs = result.expect("Signed decrement should succeed").logs;
+        let expected = CounterEvent::UpdatedSigned {
+            counter: signed,
+            operation: CounterOperation::Decrement,
+            old_value: 0,
+            new_value: -1,
+        };
+        assert!(logs.contains(&program_data_log(&expected)), "Missing event in {:#?}", logs);
+
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+        let recipient = Pubkey::new_unique();
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 42);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+        let lamports = svm.get_balance(&counter).unwrap();
+
+        let ix = instruction::close_counter(&program_id, &counter, &payer.pubkey(), &recipient);
+        let result = send_instruction(&mut svm, ix, &payer, &[]);
+        let logs = result.expect("Close transaction should succeed").logs;
+        let expected = CounterEvent::Closed { counter, recipient, lamports };
+        assert!(logs.contains(&program_data_log(&expected)), "Missing event in {:#?}", logs);
+    }
+
+    fn program_data_log(event: &CounterEvent) -> String {
+        use base64::{ Engine, engine::general_purpose::STANDARD };
+
+        format!("Program data: {}", STANDARD.encode(borsh::to_vec(event).unwrap()))
+    }
+
+    #[cfg(feature = "debug-logs")]
+    #[test]
+    fn test_debug_logs() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 42);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &CounterInstruction::IncrementCounter
+        );
+        let logs = result.expect("Increment transaction should succeed").logs;
+        let dispatch = "debug: IncrementCounter with 2 data bytes and 2 accounts";
+        let write = format!(
+            "debug: Increment read and wrote {} counter bytes",
+            CounterAccount::SIZE
+        );
+        assert!(logs.iter().any(|log| log.contains(dispatch)), "{:#?}", logs);
+        assert!(logs.iter().any(|log| log.contains(&write)), "{:#?}", logs);
+
+        // Bytes past `SIZE` are never read or written, so they don't count.
+        let mut account = svm.get_account(&counter).unwrap();
+        account.data.resize(COUNTER_ACCOUNT_SIZE + 16, 0);
+        account.lamports = svm.minimum_balance_for_rent_exemption(account.data.len());
+        svm.set_account(counter, account).unwrap();
+
+        let result = send_increment(&mut svm, program_id, &counter, &payer);
+        let logs = result.expect("Increment transaction should succeed").logs;
+        assert!(logs.iter().any(|log| log.contains(&write)), "{:#?}", logs);
+    }
+
+    #[test]
+    fn test_parse_counter_events() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 42);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &CounterInstruction::IncrementCounter
+        );
+        let logs = result.expect("Increment transaction should succeed").logs;
+        assert_eq!(
+            client::parse_counter_events(&logs),
+            vec![CounterEvent::Updated {
+                counter,
+                operation: CounterOperation::Increment,
+                old_value: 42,
+                new_value: 43,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_counter_account_pack_round_trip() {
+        let counter = CounterAccount {
+            authority: Pubkey::new_unique(),
+            max_value: 100,
+            name_len: 3,
+            ..CounterAccount::new(42)
+        };
+
+        let mut data = vec![0; CounterAccount::SIZE];
+        counter.pack(&mut data).unwrap();
+        assert_eq!(data, borsh::to_vec(&counter).unwrap());
+
+        let unpacked = CounterAccount::unpack(&data).unwrap();
+        assert_eq!(unpacked.count, 42);
+        assert_eq!(unpacked.authority, counter.authority);
+        assert_eq!(unpacked.max_value, 100);
+        assert_eq!(unpacked.name_len, 3);
+    }
+
+    #[test]
+    fn test_counter_account_round_trip_edge_values() {
+        // Edge values plus a fixed-seed xorshift sequence, so failures are reproducible.
+        let mut state = 0x9e37_79b9_7f4a_7c15u64;
+        let mut values = vec![0, 1, u64::MAX - 1, u64::MAX];
+        for _ in 0..32 {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            values.push(state);
+        }
+
+        for value in values {
+            let mut history = [0; HISTORY_LEN];
+            history[(value % HISTORY_LEN as u64) as usize] = value;
+            let key = Pubkey::new_from_array(value.to_le_bytes().repeat(4).try_into().unwrap());
+            let counter = CounterAccount {
+                count: value,
+                authority: key,
+                creator: key,
+                last_updated: value as i64,
+                update_count: value,
+                step: value,
+                is_paused: value % 2 == 1,
+                max_value: value,
+                bump: value as u8,
+                min_value: value,
+                created_slot: value,
+                history,
+                head: (value % HISTORY_LEN as u64) as u8,
+                wrapping: value % 3 == 0,
+                modulus: value,
+                require_authority: value % 5 != 0,
+                on_overflow: [OverflowPolicy::Saturate, OverflowPolicy::Wrap][(value % 2) as usize],
+                last_mutator: key,
+                lifetime_total: value,
+                pending_authority: key,
+                ..CounterAccount::new(value)
+            };
+
+            let mut data = vec![0; CounterAccount::SIZE];
+            counter.pack(&mut data).unwrap();
+            assert_eq!(borsh::to_vec(&counter).unwrap().len(), CounterAccount::SIZE);
+            assert_eq!(CounterAccount::unpack(&data).unwrap(), counter, "value {}", value);
+        }
+    }
+
+    #[test]
+    fn test_counter_account_pack_rejects_wrong_size() {
+        let size_error = ProgramError::from(CounterError::InvalidAccountSize);
+        let counter = CounterAccount::new(42);
+
+        let mut short = vec![0; CounterAccount::SIZE - 1];
+        assert_eq!(counter.pack(&mut short), Err(size_error.clone()));
+        assert_eq!(CounterAccount::unpack(&short).unwrap_err(), size_error);
+    }
+
+    #[test]
+    fn test_over_allocated_account_still_works() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 42);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let mut account = svm.get_account(&counter).unwrap();
+        account.data.resize(COUNTER_ACCOUNT_SIZE + 16, 0xff);
+        account.lamports = svm.minimum_balance_for_rent_exemption(account.data.len());
+        svm.set_account(counter, account).unwrap();
+
+        let result = send_increment(&mut svm, program_id, &counter, &payer);
+        assert!(result.is_ok(), "Increment on an over-allocated account should succeed");
+
+        let account = svm.get_account(&counter).unwrap();
+        assert_eq!(CounterAccount::unpack(&account.data).unwrap().count, 43);
+        assert_eq!(&account.data[COUNTER_ACCOUNT_SIZE..], &[0xff; 16]);
+    }
+
+    #[test]
+    fn test_unpack_legacy_account_needs_migration() {
+        let legacy = 42u64.to_le_bytes();
+        assert_eq!(legacy.len(), CounterAccount::LEGACY_SIZE);
+        assert_eq!(
+            CounterAccount::unpack(&legacy).unwrap_err(),
+            ProgramError::from(CounterError::LegacyAccountNeedsMigration)
+        );
+    }
+
+    #[test]
+    fn test_fast_count_access_matches_borsh() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 42);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let on_chain = svm.get_account(&counter_keypair.pubkey()).unwrap().data;
+        assert_eq!(CounterAccount::read_count(&on_chain).unwrap(), 42);
+
+        let mut borsh_path = CounterAccount::unpack(&on_chain).unwrap();
+        borsh_path.count = 1_000_000;
+        let borsh_bytes = borsh::to_vec(&borsh_path).unwrap();
+        assert_eq!(CounterAccount::read_count(&borsh_bytes).unwrap(), 1_000_000);
+        assert!(CounterAccount::read_count(&on_chain[..4]).is_err());
+
+        assert_eq!(CounterAccount::unpack_count(&on_chain).unwrap(), 42);
+        assert_eq!(
+            CounterAccount::unpack_count(&[0; CounterAccount::SIZE]).unwrap_err(),
+            ProgramError::from(CounterError::UninitializedAccount)
+        );
+    }
+
+    #[test]
+    fn test_zero_copy_view_matches_borsh() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 42);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+        let result = send_increment(&mut svm, program_id, &counter_keypair.pubkey(), &payer);
+        assert!(result.is_ok(), "Increment transaction should succeed");
+
+        let on_chain = svm.get_account(&counter_keypair.pubkey()).unwrap().data;
+        let borsh_view = CounterAccount::unpack(&on_chain).unwrap();
+        let view = zero_copy::CounterAccountZeroCopy::from_bytes(&on_chain).unwrap();
+        assert_eq!(view.count(), 43);
+        assert_eq!(view.count(), borsh_view.count);
+        assert_eq!(view.authority(), borsh_view.authority);
+        assert_eq!(view.last_updated(), borsh_view.last_updated);
+        assert_eq!(view.update_count(), borsh_view.update_count);
+        assert_eq!(view.history(borsh_view.head as usize - 1), Some(43));
+        assert_eq!(view.on_overflow().unwrap(), borsh_view.on_overflow);
+        assert_eq!(view.last_mutator(), borsh_view.last_mutator);
+        assert_eq!(view.lifetime_total(), borsh_view.lifetime_total);
+
+        assert!(zero_copy::CounterAccountZeroCopy::from_bytes(&on_chain[..4]).is_err());
+    }
+
+    #[test]
+    fn test_zero_copy_rejects_like_unpack() {
+        let mut unknown_version = vec![0; CounterAccount::SIZE];
+        unknown_version[0] = CURRENT_VERSION + 1;
+        let buffers = [
+            (vec![0; CounterAccount::LEGACY_SIZE], CounterError::LegacyAccountNeedsMigration),
+            (vec![0; CounterAccount::SIZE], CounterError::UninitializedAccount),
+            (vec![CURRENT_VERSION; 4], CounterError::InvalidAccountSize),
+            (unknown_version, CounterError::UnsupportedVersion),
+        ];
+
+        for (data, expected) in buffers {
+            let unpack_err = CounterAccount::unpack(&data).unwrap_err();
+            let view_err = zero_copy::CounterAccountZeroCopy::from_bytes(&data).err().unwrap();
+            assert_eq!(unpack_err, expected.into());
+            assert_eq!(view_err, unpack_err);
+        }
+    }
+
+    #[test]
+    fn test_increment_many_times() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 42);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &(CounterInstruction::SetStep { step: 5 })
+        );
+        assert!(result.is_ok(), "SetStep transaction should succeed");
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &(CounterInstruction::IncrementManyTimes { times: 10 })
+        );
+        assert!(result.is_ok(), "Batch increment should succeed");
+
+        let counter_data = get_counter(&svm, &counter);
+        assert_eq!(counter_data.count, 92);
+        assert_eq!(counter_data.update_count, 2);
+    }
+
+    #[test]
+    fn test_increment_many_times_overflow() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 42);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &(CounterInstruction::SetStep { step: 2 })
+        );
+        assert!(result.is_ok(), "SetStep transaction should succeed");
+
+        // step * times overflows before the addition.
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &(CounterInstruction::IncrementManyTimes { times: u64::MAX })
+        );
+        assert_instruction_error(
+            result,
+            InstructionError::Custom(CounterError::MulOverflow as u32)
+        );
+
+        // The product fits but the sum does not.
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &(CounterInstruction::IncrementManyTimes { times: u64::MAX / 2 })
+        );
+        assert_instruction_error(
+            result,
+            InstructionError::Custom(CounterError::AddOverflow as u32)
+        );
+        assert_eq!(get_counter(&svm, &counter).count, 42);
+    }
+
+    #[test]
+    fn test_mutation_requires_rent_exemption() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 42);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let mut account = svm.get_account(&counter).unwrap();
+        account.lamports -= 1;
+        svm.set_account(counter, account).unwrap();
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &CounterInstruction::IncrementCounter
+        );
+        assert_instruction_error(result, InstructionError::Custom(CounterError::NotRentExempt as u32));
+        assert_eq!(get_counter(&svm, &counter).count, 42);
+    }
+
+    #[test]
+    fn test_wide_and_signed_adds_require_rent_exemption() {
+        let (mut svm, payer, program_id) = setup();
+        let wide_keypair = Keypair::new();
+        let signed_keypair = Keypair::new();
+
+        let result = send_initialize_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &wide_keypair,
+            &(CounterInstruction::InitializeCounterU128 { initial_value: 42 })
+        );
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+        let result = send_initialize_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &signed_keypair,
+            &(CounterInstruction::InitializeSignedCounter { initial_value: 42 })
+        );
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        for counter in [wide_keypair.pubkey(), signed_keypair.pubkey()] {
+            let mut account = svm.get_account(&counter).unwrap();
+            account.lamports -= 1;
+            svm.set_account(counter, account).unwrap();
+        }
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &wide_keypair.pubkey(),
+            &(CounterInstruction::AddToCounterU128 { amount: 10 })
+        );
+        assert_instruction_error(result, InstructionError::Custom(CounterError::NotRentExempt as u32));
+        let account = svm.get_account(&wide_keypair.pubkey()).unwrap();
+        assert_eq!(CounterAccountU128::try_from_slice(account.data()).unwrap().count, 42);
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &signed_keypair.pubkey(),
+            &CounterInstruction::IncrementSigned
+        );
+        assert_instruction_error(result, InstructionError::Custom(CounterError::NotRentExempt as u32));
+        let account = svm.get_account(&signed_keypair.pubkey()).unwrap();
+        assert_eq!(SignedCounterAccount::try_from_slice(account.data()).unwrap().count, 42);
+    }
+
+    #[test]
+    fn test_truncated_account_is_rejected() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 42);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let mut account = svm.get_account(&counter).unwrap();
+        account.data.truncate(CounterAccount::SIZE - 1);
+        svm.set_account(counter, account).unwrap();
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &CounterInstruction::IncrementCounter
+        );
+        assert_instruction_error(
+            result,
+            InstructionError::Custom(CounterError::InvalidAccountSize as u32)
+        );
+    }
+
+    #[test]
+    fn test_decode_counter() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 42);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let account = svm.get_account(&counter).unwrap();
+        let decoded = client::decode_counter(&program_id, &account.owner, &account.data).unwrap();
+        assert_eq!(decoded.count, 42);
+        assert_eq!(decoded.authority, payer.pubkey());
+
+        let foreign_program = Pubkey::new_unique();
+        let result = client::decode_counter(&foreign_program, &account.owner, &account.data);
+        assert_eq!(result.unwrap_err(), ProgramError::IncorrectProgramId);
+
+        let mut data = account.data.clone();
+        data[0] = CURRENT_VERSION + 1;
+        let result = client::decode_counter(&program_id, &account.owner, &data);
+        assert_eq!(result.unwrap_err(), CounterError::UnsupportedVersion.into());
+    }
+
+    #[test]
+    fn test_signed_counter_goes_negative() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let result = send_initialize_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter_keypair,
+            &(CounterInstruction::InitializeSignedCounter { initial_value: 1 })
+        );
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let get_signed = |svm: &LiteSVM| {
+            let account = svm.get_account(&counter).unwrap();
+            SignedCounterAccount::try_from_slice(account.data()).unwrap().count
+        };
+
+        for _ in 0..3 {
+            let result = send_counter_instruction(
+                &mut svm,
+                program_id,
+                &payer,
+                &counter,
+                &CounterInstruction::DecrementSigned
+            );
+            assert!(result.is_ok(), "Signed decrement should succeed");
+        }
+        assert_eq!(get_signed(&svm), -2);
+
+        for _ in 0..3 {
+            let result = send_counter_instruction(
+                &mut svm,
+                program_id,
+                &payer,
+                &counter,
+                &CounterInstruction::IncrementSigned
+            );
+            assert!(result.is_ok(), "Signed increment should succeed");
+        }
+        assert_eq!(get_signed(&svm), 1);
+    }
+
+    #[test]
+    fn test_signed_counter_bounds() {
+        let (mut svm, payer, program_id) = setup();
+        let min_keypair = Keypair::new();
+        let max_keypair = Keypair::new();
+
+        for (keypair, initial_value) in [(&min_keypair, i64::MIN), (&max_keypair, i64::MAX)] {
+            let result = send_initialize_instruction(
+                &mut svm,
+                program_id,
+                &payer,
+                keypair,
+                &(CounterInstruction::InitializeSignedCounter { initial_value })
+            );
+            assert!(result.is_ok(), "Initialize transaction should succeed");
+        }
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &min_keypair.pubkey(),
+            &CounterInstruction::DecrementSigned
+        );
+        assert_instruction_error(result, InstructionError::Custom(CounterError::Underflow as u32));
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &max_keypair.pubkey(),
+            &CounterInstruction::IncrementSigned
+        );
+        assert_instruction_error(result, InstructionError::Custom(CounterError::Overflow as u32));
+    }
+
+    #[test]
+    fn test_transfer_authority() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+        let new_authority = Keypair::new();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 42);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &(CounterInstruction::TransferAuthority { new_authority: Pubkey::default() })
+        );
+        assert_instruction_error(
+            result,
+            InstructionError::Custom(CounterError::InvalidAuthority as u32)
+        );
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &(CounterInstruction::TransferAuthority { new_authority: new_authority.pubkey() })
+        );
+        assert!(result.is_ok(), "Transfer authority should succeed");
+        assert_eq!(get_counter(&svm, &counter).authority, new_authority.pubkey());
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &CounterInstruction::IncrementCounter
+        );
+        assert_instruction_error(result, InstructionError::Custom(CounterError::Unauthorized as u32));
+
+        let result = send_counter_instruction_with_authority(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &new_authority,
+            &CounterInstruction::IncrementCounter
+        );
+        assert!(result.is_ok(), "New authority should be able to increment");
+        assert_eq!(get_counter(&svm, &counter).count, 43);
+    }
+
+    #[test]
+    fn test_propose_and_accept_authority() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+        let new_authority = Keypair::new();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 42);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let result = send_instruction(
+            &mut svm,
+            instruction::propose_authority(
+                &program_id,
+                &counter,
+                &payer.pubkey(),
+                &new_authority.pubkey()
+            ),
+            &payer,
+            &[]
+        );
+        assert!(result.is_ok(), "Propose authority should succeed");
+        let counter_data = get_counter(&svm, &counter);
+        assert_eq!(counter_data.authority, payer.pubkey());
+        assert_eq!(counter_data.pending_authority, new_authority.pubkey());
+
+        let result = send_instruction(
+            &mut svm,
+            instruction::accept_authority(&program_id, &counter, &new_authority.pubkey()),
+            &payer,
+            &[&new_authority]
+        );
+        assert!(result.is_ok(), "Accept authority should succeed");
+        let counter_data = get_counter(&svm, &counter);
+        assert_eq!(counter_data.authority, new_authority.pubkey());
+        assert_eq!(counter_data.pending_authority, Pubkey::default());
+
+        let result = send_increment(&mut svm, program_id, &counter, &payer);
+        assert_instruction_error(result, InstructionError::Custom(CounterError::Unauthorized as u32));
+    }
+
+    #[test]
+    fn test_accept_authority_by_non_pending_signer_fails() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+        let new_authority = Keypair::new();
+        let stranger = Keypair::new();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 42);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let result = send_instruction(
+            &mut svm,
+            instruction::propose_authority(
+                &program_id,
+                &counter,
+                &payer.pubkey(),
+                &new_authority.pubkey()
+            ),
+            &payer,
+            &[]
+        );
+        assert!(result.is_ok(), "Propose authority should succeed");
+
+        let result = send_counter_instruction_with_authority(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &stranger,
+            &CounterInstruction::AcceptAuthority
+        );
+        assert_instruction_error(result, InstructionError::Custom(CounterError::Unauthorized as u32));
+        assert_eq!(get_counter(&svm, &counter).authority, payer.pubkey());
+
+        // Once cancelled, not even the proposed key can accept.
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &CounterInstruction::CancelAuthorityProposal
+        );
+        assert!(result.is_ok(), "Cancel proposal should succeed");
+        assert_eq!(get_counter(&svm, &counter).pending_authority, Pubkey::default());
+
+        let result = send_counter_instruction_with_authority(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &new_authority,
+            &CounterInstruction::AcceptAuthority
+        );
+        assert_instruction_error(result, InstructionError::Custom(CounterError::Unauthorized as u32));
+        assert_eq!(get_counter(&svm, &counter).authority, payer.pubkey());
+    }
+
+    #[test]
+    fn test_mutations_return_new_count() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 42);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let meta = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &CounterInstruction::IncrementCounter
+        ).expect("Increment should succeed");
+        assert_eq!(meta.return_data.program_id, program_id);
+        assert_eq!(meta.return_data.data, 43u64.to_le_bytes());
+
+        let meta = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &(CounterInstruction::AddToCounter { amount: 7 })
+        ).expect("Add should succeed");
+        assert_eq!(meta.return_data.data, 50u64.to_le_bytes());
+    }
+
+    #[test]
+    fn test_decrement_by() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 42);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &(CounterInstruction::DecrementBy { amount: 10 })
+        );
+        assert!(result.is_ok(), "Decrement by transaction should succeed");
+        assert_eq!(get_counter(&svm, &counter).count, 32);
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &(CounterInstruction::DecrementBy { amount: 33 })
+        );
+        assert_instruction_error(result, InstructionError::Custom(CounterError::Underflow as u32));
+        assert_eq!(get_counter(&svm, &counter).count, 32);
+    }
+
+    #[test]
+    fn test_decrement_exact_to_zero() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 42);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &(CounterInstruction::DecrementExact { amount: 42 })
+        );
+        assert!(result.is_ok(), "Decrement exact transaction should succeed");
+        assert_eq!(get_counter(&svm, &counter).count, 0);
+    }
+
+    #[test]
+    fn test_decrement_exact_insufficient_balance() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 42);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &(CounterInstruction::DecrementExact { amount: 43 })
+        );
+        assert_instruction_error(
+            result,
+            InstructionError::Custom(CounterError::InsufficientBalance as u32)
+        );
+        assert_eq!(get_counter(&svm, &counter).count, 42);
+    }
+
+    #[test]
+    fn test_configure() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 42);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &(CounterInstruction::Configure { value: 10, step: 5, max_value: 100 })
+        );
+        assert!(result.is_ok(), "Configure transaction should succeed");
+        let counter_data = get_counter(&svm, &counter);
+        assert_eq!(counter_data.count, 10);
+        assert_eq!(counter_data.step, 5);
+        assert_eq!(counter_data.max_value, 100);
+    }
+
+    #[test]
+    fn test_configure_rejects_invalid_combinations() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 42);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &(CounterInstruction::Configure { value: 101, step: 5, max_value: 100 })
+        );
+        assert_instruction_error(
+            result,
+            InstructionError::Custom(CounterError::MaxValueExceeded as u32)
+        );
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &(CounterInstruction::Configure { value: 10, step: 0, max_value: 100 })
+        );
+        assert_instruction_error(result, InstructionError::Custom(CounterError::InvalidStep as u32));
+
+        let counter_data = get_counter(&svm, &counter);
+        assert_eq!(counter_data.count, 42);
+        assert_eq!(counter_data.step, 1);
+        assert_eq!(counter_data.max_value, 0);
+    }
+
+    #[test]
+    fn test_increment_if_other() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let other_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+        let other = other_keypair.pubkey();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 0);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+        let result = initialize_counter(&mut svm, program_id, &payer, &other_keypair, 5);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let result = send_instruction(
+            &mut svm,
+            instruction::increment_if_other(&program_id, &counter, &payer.pubkey(), &other, 6),
+            &payer,
+            &[]
+        );
+        assert_instruction_error(
+            result,
+            InstructionError::Custom(CounterError::ConditionNotMet as u32)
+        );
+        assert_eq!(get_counter(&svm, &counter).count, 0);
+
+        let result = send_instruction(
+            &mut svm,
+            instruction::increment_if_other(&program_id, &counter, &payer.pubkey(), &other, 5),
+            &payer,
+            &[]
+        );
+        assert!(result.is_ok(), "Gated increment should succeed once the threshold is met");
+        assert_eq!(get_counter(&svm, &counter).count, 1);
+        assert_eq!(get_counter(&svm, &other).count, 5);
+
+        let foreign = Pubkey::new_unique();
+        svm.airdrop(&foreign, 1_000_000).expect("Failed to airdrop");
+        let result = send_instruction(
+            &mut svm,
+            instruction::increment_if_other(&program_id, &counter, &payer.pubkey(), &foreign, 0),
+            &payer,
+            &[]
+        );
+        assert_instruction_error(result, InstructionError::IncorrectProgramId);
+    }
+
+    #[test]
+    fn test_add_and_mul_overflow_are_distinct() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let near_max = u64::MAX - 1;
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, near_max);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &(CounterInstruction::AddToCounter { amount: 2 })
+        );
+        let logs = result.as_ref().unwrap_err().meta.logs.clone();
+        assert_instruction_error(
+            result,
+            InstructionError::Custom(CounterError::AddOverflow as u32)
+        );
+        let message = format!("Addition overflow: {} + 2", near_max);
+        assert!(logs.iter().any(|log| log.contains(&message)), "{:#?}", logs);
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &(CounterInstruction::MultiplyCounter { factor: 2 })
+        );
+        let logs = result.as_ref().unwrap_err().meta.logs.clone();
+        assert_instruction_error(
+            result,
+            InstructionError::Custom(CounterError::MulOverflow as u32)
+        );
+        let message = format!("Multiplication overflow: {} * 2", near_max);
+        assert!(logs.iter().any(|log| log.contains(&message)), "{:#?}", logs);
+
+        assert_eq!(get_counter(&svm, &counter).count, near_max);
+    }
+
+    #[test]
+    fn test_weighted_increment() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 2);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &(CounterInstruction::SetStep { step: 5 })
+        );
+        assert!(result.is_ok(), "Set step transaction should succeed");
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &(CounterInstruction::WeightedIncrement { weight: 3 })
+        );
+        assert!(result.is_ok(), "Weighted increment transaction should succeed");
+        assert_eq!(get_counter(&svm, &counter).count, 17);
+    }
+
+    #[test]
+    fn test_weighted_increment_multiply_overflow() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 0);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &(CounterInstruction::SetStep { step: 2 })
+        );
+        assert!(result.is_ok(), "Set step transaction should succeed");
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &(CounterInstruction::WeightedIncrement { weight: u64::MAX })
+        );
+        assert_instruction_error(
+            result,
+            InstructionError::Custom(CounterError::MulOverflow as u32)
+        );
+        assert_eq!(get_counter(&svm, &counter).count, 0);
+    }
+
+    #[test]
+    fn test_account_space_matches_size() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let wide_keypair = Keypair::new();
+        let signed_keypair = Keypair::new();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 42);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+        let result = send_initialize_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &wide_keypair,
+            &(CounterInstruction::InitializeCounterU128 { initial_value: 42 })
+        );
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+        let result = send_initialize_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &signed_keypair,
+            &(CounterInstruction::InitializeSignedCounter { initial_value: 42 })
+        );
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let data_len = |svm: &LiteSVM, key: &Pubkey| svm.get_account(key).unwrap().data().len();
+        assert_eq!(data_len(&svm, &counter_keypair.pubkey()), CounterAccount::SIZE);
+        assert_eq!(data_len(&svm, &wide_keypair.pubkey()), CounterAccountU128::SIZE);
+        assert_eq!(data_len(&svm, &signed_keypair.pubkey()), SignedCounterAccount::SIZE);
+
+        let counter_data = get_counter(&svm, &counter_keypair.pubkey());
+        assert_eq!(borsh::to_vec(&counter_data).unwrap().len(), CounterAccount::SIZE);
+    }
+
+    #[test]
+    fn test_multiply_counter() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 6);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &(CounterInstruction::MultiplyCounter { factor: 7 })
+        );
+        assert!(result.is_ok(), "Multiply transaction should succeed");
+        assert_eq!(get_counter(&svm, &counter).count, 42);
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &(CounterInstruction::MultiplyCounter { factor: 0 })
+        );
+        assert!(result.is_ok(), "Multiply by zero should succeed");
+        assert_eq!(get_counter(&svm, &counter).count, 0);
+    }
+
+    #[test]
+    fn test_multiply_overflow_fails() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let initial_value = u64::MAX / 2 + 1;
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, initial_value);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &(CounterInstruction::MultiplyCounter { factor: 2 })
+        );
+        assert_instruction_error(
+            result,
+            InstructionError::Custom(CounterError::MulOverflow as u32)
+        );
+        assert_eq!(get_counter(&svm, &counter).count, initial_value);
+    }
+
+    #[test]
+    fn test_migrate_legacy_counter() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let legacy_account = Account {
+            lamports: svm.minimum_balance_for_rent_exemption(CounterAccount::LEGACY_SIZE),
+            data: 42u64.to_le_bytes().to_vec(),
+            owner: program_id,
+            executable: false,
+            rent_epoch: 0,
+        };
+        svm.set_account(counter, legacy_account).unwrap();
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &CounterInstruction::IncrementCounter
+        );
+        assert_instruction_error(
+            result,
+            InstructionError::Custom(CounterError::LegacyAccountNeedsMigration as u32)
+        );
+
+        let result = send_instruction(
+            &mut svm,
+            instruction::migrate_counter(&program_id, &counter, &payer.pubkey()),
+            &payer,
+            &[&counter_keypair]
+        );
+        assert!(result.is_ok(), "Migrate transaction should succeed");
+
+        let account = svm.get_account(&counter).unwrap();
+        assert_eq!(account.data().len(), CounterAccount::SIZE);
+        assert_eq!(account.lamports(), svm.minimum_balance_for_rent_exemption(CounterAccount::SIZE));
+
+        let counter_data = get_counter(&svm, &counter);
+        assert_eq!(counter_data.version, CURRENT_VERSION);
+        assert_eq!(counter_data.count, 42);
+        assert_eq!(counter_data.authority, payer.pubkey());
+        assert_eq!(counter_data.step, 1);
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &CounterInstruction::IncrementCounter
+        );
+        assert!(result.is_ok(), "Increment after migration should succeed");
+        assert_eq!(get_counter(&svm, &counter).count, 43);
+
+        let result = send_instruction(
+            &mut svm,
+            instruction::migrate_counter(&program_id, &counter, &payer.pubkey()),
+            &payer,
+            &[&counter_keypair]
+        );
+        assert_instruction_error(
+            result,
+            InstructionError::Custom(CounterError::InvalidAccountSize as u32)
+        );
+    }
+
+    #[test]
+    fn test_migrate_without_counter_signature_fails() {
+        let (mut svm, payer, program_id) = setup();
+        let counter = Pubkey::new_unique();
+
+        let legacy_account = Account {
+            lamports: svm.minimum_balance_for_rent_exemption(CounterAccount::LEGACY_SIZE),
+            data: 42u64.to_le_bytes().to_vec(),
+            owner: program_id,
+            executable: false,
+            rent_epoch: 0,
+        };
+        svm.set_account(counter, legacy_account).unwrap();
+
+        let mut instruction = instruction::migrate_counter(&program_id, &counter, &payer.pubkey());
+        instruction.accounts[0].is_signer = false;
+        let result = send_instruction(&mut svm, instruction, &payer, &[]);
+        assert_instruction_error(result, InstructionError::MissingRequiredSignature);
+
+        let account = svm.get_account(&counter).unwrap();
+        assert_eq!(account.data(), 42u64.to_le_bytes());
+    }
+
+    #[test]
+    fn test_initialize_above_max_value_fails() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let result = send_instruction(
+            &mut svm,
+            instruction::initialize_counter_with_bounds(
+                &program_id,
+                &counter,
+                &payer.pubkey(),
+                51,
+                0,
+                50
+            ),
+            &payer,
+            &[&counter_keypair]
+        );
+        let logs = result.as_ref().unwrap_err().meta.logs.clone();
+        assert!(
+            logs.iter().any(|log| log.contains("Initial value 51 is above max value 50")),
+            "{:#?}",
+            logs
+        );
+        assert_instruction_error(
+            result,
+            InstructionError::Custom(CounterError::MaxValueExceeded as u32)
+        );
+        assert!(svm.get_account(&counter).is_none_or(|account| account.data.is_empty()));
+    }
+
+    #[test]
+    fn test_min_value_floor() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let result = send_instruction(
+            &mut svm,
+            instruction::initialize_counter_with_bounds(
+                &program_id,
+                &counter,
+                &payer.pubkey(),
+                9,
+                10,
+                0
+            ),
+            &payer,
+            &[&counter_keypair]
+        );
+        assert_instruction_error(
+            result,
+            InstructionError::Custom(CounterError::MinValueViolated as u32)
+        );
+
+        let result = send_instruction(
+            &mut svm,
+            instruction::initialize_counter_with_bounds(
+                &program_id,
+                &counter,
+                &payer.pubkey(),
+                15,
+                10,
+                0
+            ),
+            &payer,
+            &[&counter_keypair]
+        );
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+        assert_eq!(get_counter(&svm, &counter).min_value, 10);
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &(CounterInstruction::SubtractFromCounter { amount: 4 })
+        );
+        assert!(result.is_ok(), "Subtract above the floor should succeed");
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &CounterInstruction::DecrementCounter
+        );
+        assert!(result.is_ok(), "Decrement down to the floor should succeed");
+        assert_eq!(get_counter(&svm, &counter).count, 10);
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &CounterInstruction::DecrementCounter
+        );
+        assert_instruction_error(
+            result,
+            InstructionError::Custom(CounterError::MinValueViolated as u32)
+        );
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &(CounterInstruction::DecrementBy { amount: 1 })
+        );
+        assert_instruction_error(
+            result,
+            InstructionError::Custom(CounterError::MinValueViolated as u32)
+        );
+        assert_eq!(get_counter(&svm, &counter).count, 10);
+    }
+
+    #[test]
+    fn test_compare_and_set() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 42);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &(CounterInstruction::CompareAndSet { expected: 42, new_value: 100 })
+        );
+        assert!(result.is_ok(), "Compare and set with a matching value should succeed");
+        assert_eq!(get_counter(&svm, &counter).count, 100);
+    }
+
+    #[test]
+    fn test_compare_and_set_stale_value_fails() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 42);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &(CounterInstruction::CompareAndSet { expected: 41, new_value: 100 })
+        );
+        assert_instruction_error(
+            result,
+            InstructionError::Custom(CounterError::CompareFailed as u32)
+        );
+        assert_eq!(get_counter(&svm, &counter).count, 42);
+    }
+
+    #[test]
+    fn test_set_checked() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 42);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let result = send_instruction(
+            &mut svm,
+            instruction::set_checked(&program_id, &counter, &payer.pubkey(), 42, 100),
+            &payer,
+            &[]
+        );
+        assert!(result.is_ok(), "Set checked with the current value should succeed");
+        assert!(result.unwrap().logs.iter().any(|log| log.contains("Counter set from 42 to 100")));
+        assert_eq!(get_counter(&svm, &counter).count, 100);
+    }
+
+    #[test]
+    fn test_set_checked_stale_value_fails() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 42);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let result = send_instruction(
+            &mut svm,
+            instruction::set_checked(&program_id, &counter, &payer.pubkey(), 41, 100),
+            &payer,
+            &[]
+        );
+        let logs = result.as_ref().unwrap_err().meta.logs.clone();
+        assert!(
+            logs.iter().any(|log| log.contains("Stale value: counter is 42, caller expected 41")),
+            "{:#?}",
+            logs
+        );
+        assert_instruction_error(result, InstructionError::Custom(CounterError::StaleValue as u32));
+        assert_eq!(get_counter(&svm, &counter).count, 42);
+    }
+
+    #[test]
+    fn test_transfer_count() {
+        let (mut svm, payer, program_id) = setup();
+        let source_keypair = Keypair::new();
+        let source = source_keypair.pubkey();
+        let destination_keypair = Keypair::new();
+        let destination = destination_keypair.pubkey();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &source_keypair, 50);
+        assert!(result.is_ok(), "Initialize source transaction should succeed");
+        let result = initialize_counter(&mut svm, program_id, &payer, &destination_keypair, 10);
+        assert!(result.is_ok(), "Initialize destination transaction should succeed");
+
+        let result = send_instruction(
+            &mut svm,
+            instruction::transfer_count(&program_id, &source, &destination, &payer.pubkey(), 20),
+            &payer,
+            &[]
+        );
+        assert!(result.is_ok(), "Transfer transaction should succeed");
+        assert_eq!(get_counter(&svm, &source).count, 30);
+        assert_eq!(get_counter(&svm, &destination).count, 30);
+    }
+
+    #[test]
+    fn test_transfer_count_overflow_changes_nothing() {
+        let (mut svm, payer, program_id) = setup();
+        let source_keypair = Keypair::new();
+        let source = source_keypair.pubkey();
+        let destination_keypair = Keypair::new();
+        let destination = destination_keypair.pubkey();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &source_keypair, 50);
+        assert!(result.is_ok(), "Initialize source transaction should succeed");
+        let result = initialize_counter(
+            &mut svm,
+            program_id,
+            &payer,
+            &destination_keypair,
+            u64::MAX
+        );
+        assert!(result.is_ok(), "Initialize destination transaction should succeed");
+
+        let result = send_instruction(
+            &mut svm,
+            instruction::transfer_count(&program_id, &source, &destination, &payer.pubkey(), 20),
+            &payer,
+            &[]
+        );
+        assert_instruction_error(
+            result,
+            InstructionError::Custom(CounterError::AddOverflow as u32)
+        );
+        assert_eq!(get_counter(&svm, &source).count, 50);
+        assert_eq!(get_counter(&svm, &destination).count, u64::MAX);
+    }
+
+    #[test]
+    fn test_creator_and_created_slot() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+        svm.warp_to_slot(7);
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 42);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let counter_data = get_counter(&svm, &counter);
+        assert_eq!(counter_data.creator, payer.pubkey());
+        assert_eq!(counter_data.created_slot, 7);
+
+        let new_authority = Keypair::new();
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &(CounterInstruction::TransferAuthority { new_authority: new_authority.pubkey() })
+        );
+        assert!(result.is_ok(), "Transfer authority should succeed");
+        svm.warp_to_slot(9);
+        let result = send_counter_instruction_with_authority(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &new_authority,
+            &CounterInstruction::IncrementCounter
+        );
+        assert!(result.is_ok(), "Increment transaction should succeed");
+
+        let counter_data = get_counter(&svm, &counter);
+        assert_eq!(counter_data.creator, payer.pubkey());
+        assert_eq!(counter_data.created_slot, 7);
+    }
+
+    #[test]
+    fn test_history_keeps_last_values() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 0);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        for _ in 0..10 {
+            let result = send_counter_instruction(
+                &mut svm,
+                program_id,
+                &payer,
+                &counter,
+                &CounterInstruction::IncrementCounter
+            );
+            assert!(result.is_ok(), "Increment transaction should succeed");
+        }
+
+        let counter_data = get_counter(&svm, &counter);
+        assert_eq!(counter_data.head, 2);
+        assert_eq!(counter_data.history, [9, 10, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(counter_data.recent_history(), [3, 4, 5, 6, 7, 8, 9, 10]);
+    }
+
+    #[test]
+    fn test_history_skips_admin_operations() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 0);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+        let result = send_increment(&mut svm, program_id, &counter, &payer);
+        assert!(result.is_ok(), "Increment transaction should succeed");
+        let history = get_counter(&svm, &counter).recent_history();
+
+        for instruction in [CounterInstruction::PauseCounter, CounterInstruction::ResumeCounter] {
+            let result = send_counter_instruction(&mut svm, program_id, &payer, &counter, &instruction);
+            assert!(result.is_ok(), "{:?} should succeed", instruction);
+        }
+
+        let counter_data = get_counter(&svm, &counter);
+        assert_eq!(counter_data.update_count, 3);
+        assert_eq!(counter_data.recent_history(), history);
+    }
+
+    #[test]
+    fn test_get_count_simulation() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 42);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let instruction = instruction::get_count(&program_id, &counter);
+        assert!(!instruction.accounts[0].is_writable);
+
+        let message = Message::new(&[instruction], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, svm.latest_blockhash());
+        let simulated = svm.simulate_transaction(transaction).expect("Simulation should succeed");
+        assert_eq!(simulated.meta.return_data.program_id, program_id);
+        assert_eq!(simulated.meta.return_data.data, 42u64.to_le_bytes());
+
+        let counter_data = get_counter(&svm, &counter);
+        assert_eq!(counter_data.count, 42);
+        assert_eq!(counter_data.update_count, 0);
+    }
+
+    #[test]
+    fn test_read_count_byte_order() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 0x0102);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let cases = [(false, 0x0102u64.to_le_bytes()), (true, 0x0102u64.to_be_bytes())];
+        for (big_endian, expected) in cases {
+            let instruction = instruction::read_count(&program_id, &counter, big_endian);
+            let message = Message::new(&[instruction], Some(&payer.pubkey()));
+            let transaction = Transaction::new(&[&payer], message, svm.latest_blockhash());
+            let simulated = svm
+                .simulate_transaction(transaction)
+                .expect("Simulation should succeed");
+            assert_eq!(simulated.meta.return_data.data, expected);
+        }
+    }
+
+    #[test]
+    fn test_get_metadata_returns_whole_account() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 42);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &(CounterInstruction::SetStep { step: 5 })
+        );
+        assert!(result.is_ok(), "Set step transaction should succeed");
+
+        let instruction = instruction::get_metadata(&program_id, &counter);
+        assert!(!instruction.accounts[0].is_writable);
+
+        let message = Message::new(&[instruction], Some(&payer.pubkey()));
+        let transaction = Transaction::new(&[&payer], message, svm.latest_blockhash());
+        let simulated = svm.simulate_transaction(transaction).expect("Simulation should succeed");
+        let returned = CounterAccount::try_from_slice(&simulated.meta.return_data.data).unwrap();
+        assert_eq!(returned, get_counter(&svm, &counter));
+        assert_eq!(returned.step, 5);
+    }
+
+    #[test]
+    fn test_count_since_baseline() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 42);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        for (baseline, expected) in [(40u64, 2u64), (42, 0), (50, 0)] {
+            let instruction = instruction::count_since_baseline(&program_id, &counter, baseline);
+            assert!(!instruction.accounts[0].is_writable);
+
+            let message = Message::new(&[instruction], Some(&payer.pubkey()));
+            let transaction = Transaction::new(&[&payer], message, svm.latest_blockhash());
+            let simulated = svm
+                .simulate_transaction(transaction)
+                .expect("Simulation should succeed");
+            assert_eq!(simulated.meta.return_data.data, expected.to_le_bytes());
+        }
+        assert_eq!(get_counter(&svm, &counter).update_count, 0);
+    }
+
+    #[test]
+    fn test_assert_count() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 42);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let instruction = instruction::assert_count(&program_id, &counter, 42);
+        assert!(!instruction.accounts[0].is_writable);
+        let result = send_instruction(&mut svm, instruction, &payer, &[]);
+        assert!(result.is_ok(), "Matching assertion should succeed");
+        assert_eq!(get_counter(&svm, &counter).update_count, 0);
+    }
+
+    #[test]
+    fn test_assert_count_mismatch_fails() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 42);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        // The increment in the same transaction is rolled back with the failed assertion.
+        let result = svm.send_transaction(
+            Transaction::new(
+                &[&payer],
+                Message::new(
+                    &[
+                        instruction::increment_counter(&program_id, &counter, &payer.pubkey()),
+                        instruction::assert_count(&program_id, &counter, 42),
+                    ],
+                    Some(&payer.pubkey())
+                ),
+                svm.latest_blockhash()
+            )
+        );
+        let logs = result.as_ref().unwrap_err().meta.logs.clone();
+        assert!(
+            logs.iter().any(|log| log.contains("Assertion failed: counter is 43, expected 42")),
+            "{:#?}",
+            logs
+        );
+        assert_eq!(
+            result.unwrap_err().err,
+            TransactionError::InstructionError(
+                1,
+                InstructionError::Custom(CounterError::AssertionFailed as u32)
+            )
+        );
+        assert_eq!(get_counter(&svm, &counter).count, 42);
+    }
+
+    #[test]
+    fn test_named_counter() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let result = send_instruction(
+            &mut svm,
+            instruction::initialize_named_counter(
+                &program_id,
+                &counter,
+                &payer.pubkey(),
+                42,
+                "visits"
+            ),
+            &payer,
+            &[&counter_keypair]
+        );
+        assert!(result.is_ok(), "Named initialize transaction should succeed");
+
+        let counter_data = get_counter(&svm, &counter);
+        assert_eq!(counter_data.name(), "visits");
+        assert_eq!(counter_data.count, 42);
+    }
+
+    #[test]
+    fn test_name_too_long_fails() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+        let name = "x".repeat(MAX_NAME_LEN + 1);
+
+        let result = send_instruction(
+            &mut svm,
+            instruction::initialize_named_counter(
+                &program_id,
+                &counter,
+                &payer.pubkey(),
+                42,
+                &name
+            ),
+            &payer,
+            &[&counter_keypair]
+        );
+        assert_instruction_error(result, InstructionError::Custom(CounterError::NameTooLong as u32));
+        assert!(svm.get_account(&counter).is_none());
+    }
+
+    #[test]
+    fn test_wrapping_increment() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, u64::MAX);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+        assert!(!get_counter(&svm, &counter).wrapping);
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &(CounterInstruction::SetWrapping { wrapping: true })
+        );
+        assert!(result.is_ok(), "Set wrapping transaction should succeed");
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &CounterInstruction::IncrementCounter
+        );
+        assert!(result.is_ok(), "Wrapping increment should succeed");
+        assert_eq!(get_counter(&svm, &counter).count, 0);
+    }
+
+    #[test]
+    fn test_overflow_policy() {
+        let (mut svm, payer, program_id) = setup();
+
+        for (on_overflow, expected) in [
+            (OverflowPolicy::Error, None),
+            (OverflowPolicy::Saturate, Some(u64::MAX)),
+            (OverflowPolicy::Wrap, Some(0)),
+        ] {
+            let counter_keypair = Keypair::new();
+            let counter = counter_keypair.pubkey();
+
+            let result = send_instruction(
+                &mut svm,
+                instruction::initialize_counter_with_overflow(
+                    &program_id,
+                    &counter,
+                    &payer.pubkey(),
+                    u64::MAX,
+                    on_overflow
+                ),
+                &payer,
+                &[&counter_keypair]
+            );
+            assert!(result.is_ok(), "Initialize transaction should succeed");
+            assert_eq!(get_counter(&svm, &counter).on_overflow, on_overflow);
+
+            let result = send_increment(&mut svm, program_id, &counter, &payer);
+            match expected {
+                Some(count) => {
+                    assert!(result.is_ok(), "{:?} increment should succeed", on_overflow);
+                    assert_eq!(get_counter(&svm, &counter).count, count);
+                }
+                None => {
+                    assert_instruction_error(
+                        result,
+                        InstructionError::Custom(CounterError::Overflow as u32)
+                    );
+                    assert_eq!(get_counter(&svm, &counter).count, u64::MAX);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_overflow_policy_multi_step_increments() {
+        let (mut svm, payer, program_id) = setup();
+
+        for (on_overflow, expected) in [
+            (OverflowPolicy::Error, [None; 3]),
+            (OverflowPolicy::Saturate, [Some(u64::MAX); 3]),
+            (OverflowPolicy::Wrap, [Some(1), Some(2), Some(0)]),
+        ] {
+            let counter_keypairs = [Keypair::new(), Keypair::new(), Keypair::new()];
+            for keypair in &counter_keypairs {
+                let result = send_initialize_instruction(
+                    &mut svm,
+                    program_id,
+                    &payer,
+                    keypair,
+                    &(CounterInstruction::InitializeCounter {
+                        initial_value: u64::MAX,
+                        max_value: 0,
+                        min_value: 1,
+                        name: "near max".to_string(),
+                        on_overflow,
+                    })
+                );
+                assert!(result.is_ok(), "Initialize transaction should succeed");
+            }
+            let [many, weighted, batched] = counter_keypairs.map(|keypair| keypair.pubkey());
+            let counter_data = get_counter(&svm, &many);
+            assert_eq!(counter_data.on_overflow, on_overflow);
+            assert_eq!(counter_data.min_value, 1);
+            assert_eq!(counter_data.name(), "near max");
+
+            let results = [
+                send_counter_instruction(
+                    &mut svm,
+                    program_id,
+                    &payer,
+                    &many,
+                    &(CounterInstruction::IncrementManyTimes { times: 2 })
+                ),
+                send_counter_instruction(
+                    &mut svm,
+                    program_id,
+                    &payer,
+                    &weighted,
+                    &(CounterInstruction::WeightedIncrement { weight: 3 })
+                ),
+                send_instruction(
+                    &mut svm,
+                    instruction::batch_increment(&program_id, &payer.pubkey(), &[batched]),
+                    &payer,
+                    &[]
+                ),
+            ];
+            let errors = [
+                CounterError::AddOverflow,
+                CounterError::AddOverflow,
+                CounterError::Overflow,
+            ];
+
+            for (index, result) in results.into_iter().enumerate() {
+                let counter = [many, weighted, batched][index];
+                match expected[index] {
+                    Some(count) => {
+                        assert!(result.is_ok(), "{:?} increment {} failed", on_overflow, index);
+                        assert_eq!(get_counter(&svm, &counter).count, count);
+                    }
+                    None => {
+                        assert_instruction_error(
+                            result,
+                            InstructionError::Custom(errors[index] as u32)
+                        );
+                        assert_eq!(get_counter(&svm, &counter).count, u64::MAX);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_modulus_increment_cycles() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 0);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &(CounterInstruction::SetModulus { modulus: 3 })
+        );
+        assert!(result.is_ok(), "Set modulus transaction should succeed");
+
+        for expected in [1, 2, 0, 1] {
+            let result = send_counter_instruction(
+                &mut svm,
+                program_id,
+                &payer,
+                &counter,
+                &CounterInstruction::IncrementCounter
+            );
+            assert!(result.is_ok(), "Modulus increment should succeed");
+            assert_eq!(get_counter(&svm, &counter).count, expected);
+        }
+    }
+
+    #[test]
+    fn test_read_only_counter_is_rejected() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 42);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let instruction = Instruction::new_with_bytes(
+            program_id,
+            &CounterInstruction::IncrementCounter.pack(),
+            vec![
+                AccountMeta::new_readonly(counter, false),
+                AccountMeta::new_readonly(payer.pubkey(), true)
+            ]
+        );
+        let result = send_instruction(&mut svm, instruction, &payer, &[]);
+        assert_instruction_error(result, InstructionError::InvalidArgument);
+        assert_eq!(get_counter(&svm, &counter).count, 42);
+    }
+
+    #[test]
+    fn test_reinitialize_counter() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 42);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+        for instruction in [
+            CounterInstruction::IncrementCounter,
+            CounterInstruction::SetStep { step: 5 },
+            CounterInstruction::PauseCounter,
+        ] {
+            let result = send_counter_instruction(&mut svm, program_id, &payer, &counter, &instruction);
+            assert!(result.is_ok(), "{:?} should succeed", instruction);
+        }
+
+        let intruder = Keypair::new();
+        svm.airdrop(&intruder.pubkey(), 1_000_000_000).expect("Failed to airdrop");
+        let result = send_counter_instruction_with_authority(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &intruder,
+            &(CounterInstruction::Reinitialize { initial_value: 7 })
+        );
+        assert_instruction_error(result, InstructionError::Custom(CounterError::Unauthorized as u32));
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &(CounterInstruction::Reinitialize { initial_value: 7 })
+        );
+        assert!(result.is_ok(), "Reinitialize transaction should succeed");
+
+        let counter_data = get_counter(&svm, &counter);
+        assert_eq!(counter_data.count, 7);
+        assert_eq!(counter_data.step, 1);
+        assert_eq!(counter_data.update_count, 0);
+        assert!(!counter_data.is_paused);
+        assert_eq!(counter_data.authority, payer.pubkey());
+    }
+
+    #[test]
+    fn test_reinitialize_keeps_configuration() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let result = send_initialize_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter_keypair,
+            &(CounterInstruction::InitializeCounter {
+                initial_value: 40,
+                max_value: 50,
+                min_value: 10,
+                name: "visits".to_string(),
+                on_overflow: OverflowPolicy::Error,
+            })
+        );
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        for (initial_value, error) in [
+            (51, CounterError::MaxValueExceeded),
+            (9, CounterError::MinValueViolated),
+        ] {
+            let result = send_counter_instruction(
+                &mut svm,
+                program_id,
+                &payer,
+                &counter,
+                &(CounterInstruction::Reinitialize { initial_value })
+            );
+            assert_instruction_error(result, InstructionError::Custom(error as u32));
+        }
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &(CounterInstruction::Reinitialize { initial_value: 20 })
+        );
+        assert!(result.is_ok(), "Reinitialize within the caps should succeed");
+        let counter_data = get_counter(&svm, &counter);
+        assert_eq!(counter_data.count, 20);
+        assert_eq!(counter_data.max_value, 50);
+        assert_eq!(counter_data.min_value, 10);
+        assert_eq!(counter_data.name(), "visits");
+
+        let public_keypair = Keypair::new();
+        let public = public_keypair.pubkey();
+        let result = send_initialize_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &public_keypair,
+            &(CounterInstruction::InitializeCounterWithAccess {
+                initial_value: 0,
+                require_authority: false,
+            })
+        );
+        assert!(result.is_ok(), "Initialize public counter should succeed");
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &public,
+            &(CounterInstruction::Reinitialize { initial_value: 1 })
+        );
+        assert!(result.is_ok(), "Reinitialize public counter should succeed");
+        assert!(!get_counter(&svm, &public).require_authority);
+
+        let saturating_keypair = Keypair::new();
+        let saturating = saturating_keypair.pubkey();
+        let result = send_instruction(
+            &mut svm,
+            instruction::initialize_counter_with_overflow(
+                &program_id,
+                &saturating,
+                &payer.pubkey(),
+                0,
+                OverflowPolicy::Saturate
+            ),
+            &payer,
+            &[&saturating_keypair]
+        );
+        assert!(result.is_ok(), "Initialize saturating counter should succeed");
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &saturating,
+            &(CounterInstruction::Reinitialize { initial_value: u64::MAX })
+        );
+        assert!(result.is_ok(), "Reinitialize saturating counter should succeed");
+        assert_eq!(get_counter(&svm, &saturating).on_overflow, OverflowPolicy::Saturate);
+        let result = send_increment(&mut svm, program_id, &saturating, &payer);
+        assert!(result.is_ok(), "Saturating increment should still succeed");
+        assert_eq!(get_counter(&svm, &saturating).count, u64::MAX);
+    }
+
+    #[test]
+    fn test_counter_rent() {
+        let rent = Rent::default();
+        assert_eq!(counter_rent(&rent), rent.minimum_balance(COUNTER_ACCOUNT_SIZE));
+
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 42);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+        assert_eq!(
+            svm.get_balance(&counter_keypair.pubkey()).unwrap(),
+            counter_rent(&svm.get_sysvar::<Rent>())
+        );
+    }
+
+    #[test]
+    fn test_counter_account_defaults() {
+        let counter_data = CounterAccount::default();
+        assert_eq!(counter_data.count, 0);
+        assert_eq!(counter_data.version, CURRENT_VERSION);
+        assert_eq!(counter_data.step, 1);
+
+        let counter_data = CounterAccount::new(42);
+        assert_eq!(counter_data.count, 42);
+        assert_eq!(counter_data.step, 1);
+        assert!(!counter_data.is_paused);
+    }
+
+    #[test]
+    fn test_counter_account_size_constant() {
+        let data = borsh::to_vec(&CounterAccount::default()).unwrap();
+        assert_eq!(data.len(), COUNTER_ACCOUNT_SIZE);
+        assert!(CounterAccount::unpack(&data).is_ok());
+        assert_eq!(COUNT_OFFSET, DISCRIMINATOR_LEN);
+        assert_eq!(&data[..DISCRIMINATOR_LEN], &[CURRENT_VERSION]);
+    }
+
+    #[test]
+    fn test_clamp_counter() {
+        let (mut svm, payer, program_id) = setup();
+
+        for (initial_value, expected) in [(5, 10), (15, 15), (25, 20)] {
+            let counter_keypair = Keypair::new();
+            let counter = counter_keypair.pubkey();
+
+            let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, initial_value);
+            assert!(result.is_ok(), "Initialize transaction should succeed");
+
+            let result = send_counter_instruction(
+                &mut svm,
+                program_id,
+                &payer,
+                &counter,
+                &(CounterInstruction::ClampCounter { low: 10, high: 20 })
+            );
+            assert!(result.is_ok(), "Clamp transaction should succeed");
+            assert_eq!(get_counter(&svm, &counter).count, expected);
+        }
+    }
+
+    #[test]
+    fn test_clamp_inverted_range_fails() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 15);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &(CounterInstruction::ClampCounter { low: 20, high: 10 })
+        );
+        assert_instruction_error(result, InstructionError::Custom(CounterError::InvalidRange as u32));
+        assert_eq!(get_counter(&svm, &counter).count, 15);
+    }
+
+    #[test]
+    fn test_batch_increment() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypairs = [Keypair::new(), Keypair::new(), Keypair::new()];
+        let counters: Vec<Pubkey> = counter_keypairs.iter().map(|keypair| keypair.pubkey()).collect();
+
+        for (keypair, initial_value) in counter_keypairs.iter().zip([1, 10, 100]) {
+            let result = initialize_counter(&mut svm, program_id, &payer, keypair, initial_value);
+            assert!(result.is_ok(), "Initialize transaction should succeed");
+        }
+
+        let result = send_instruction(
+            &mut svm,
+            instruction::batch_increment(&program_id, &payer.pubkey(), &counters),
+            &payer,
+            &[]
+        );
+        assert!(result.is_ok(), "Batch increment should succeed");
+        assert_eq!(get_counter(&svm, &counters[0]).count, 2);
+        assert_eq!(get_counter(&svm, &counters[1]).count, 11);
+        assert_eq!(get_counter(&svm, &counters[2]).count, 101);
+    }
+
+    #[test]
+    fn test_batch_increment_aborts_on_bad_counter() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 1);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let foreign = Pubkey::new_unique();
+        svm.airdrop(&foreign, 1_000_000).expect("Failed to airdrop");
+        let result = send_instruction(
+            &mut svm,
+            instruction::batch_increment(&program_id, &payer.pubkey(), &[counter, foreign]),
+            &payer,
+            &[]
+        );
+        assert_instruction_error(result, InstructionError::IncorrectProgramId);
+        assert_eq!(get_counter(&svm, &counter).count, 1);
+    }
+
+    #[test]
+    fn test_batch_increment_follows_counter_settings() {
+        let (mut svm, payer, program_id) = setup();
+        let stepped_keypair = Keypair::new();
+        let cycling_keypair = Keypair::new();
+        let stepped = stepped_keypair.pubkey();
+        let cycling = cycling_keypair.pubkey();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &stepped_keypair, 1);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+        let result = initialize_counter(&mut svm, program_id, &payer, &cycling_keypair, 2);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &stepped,
+            &(CounterInstruction::SetStep { step: 5 })
+        );
+        assert!(result.is_ok(), "Set step transaction should succeed");
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &cycling,
+            &(CounterInstruction::SetModulus { modulus: 3 })
+        );
+        assert!(result.is_ok(), "Set modulus transaction should succeed");
+
+        let result = send_instruction(
+            &mut svm,
+            instruction::batch_increment(&program_id, &payer.pubkey(), &[stepped, cycling]),
+            &payer,
+            &[]
+        );
+        assert!(result.is_ok(), "Batch increment should succeed");
+        assert_eq!(get_counter(&svm, &stepped).count, 6);
+        assert_eq!(get_counter(&svm, &cycling).count, 0);
+    }
+
+    #[test]
+    fn test_batch_increment_rejects_duplicate_counter() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 1);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let result = send_instruction(
+            &mut svm,
+            instruction::batch_increment(&program_id, &payer.pubkey(), &[counter, counter]),
+            &payer,
+            &[]
         );
+        assert_instruction_error(
+            result,
+            InstructionError::Custom(CounterError::DuplicateAccount as u32)
+        );
+        assert_eq!(get_counter(&svm, &counter).count, 1);
+    }
 
-        assert_eq!(counter.count, 42);
-        println!("Counter initialized successfully with value: {}", counter.count);
+    #[test]
+    fn test_increment_logs_owner_mismatch() {
+        let (mut svm, payer, program_id) = setup();
 
-        println!("Testing counter incremenet...");
-        let increment_instruction_data = borsh
-            ::to_vec(&CounterInstruction::IncrementCounter)
-            .expect("Failed to serialize instruction");
-        let increment_instruction = Instruction::new_with_bytes(
+        let foreign = Pubkey::new_unique();
+        svm.airdrop(&foreign, 1_000_000).expect("Failed to airdrop");
+        let result = send_instruction(
+            &mut svm,
+            instruction::increment_counter(&program_id, &foreign, &payer.pubkey()),
+            &payer,
+            &[]
+        );
+        let logs = result.as_ref().unwrap_err().meta.logs.clone();
+        assert_instruction_error(result, InstructionError::IncorrectProgramId);
+
+        let message = format!(
+            "Counter account must be owned by {}, but is owned by {}",
             program_id,
-            &increment_instruction_data,
-            vec![AccountMeta::new(counter_keypair.pubkey(), true)]
+            system_program::id()
         );
-        let message = Message::new(&[increment_instruction], Some(&payer.pubkey()));
-        let transaction = Transaction::new(
-            &[&payer, &counter_keypair],
-            message,
-            svm.latest_blockhash()
+        assert!(logs.iter().any(|log| log.contains(&message)), "{:#?}", logs);
+    }
+
+    #[test]
+    fn test_initialize_data_on_precreated_account() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let space = CounterAccount::SIZE;
+        let lamports = svm.minimum_balance_for_rent_exemption(space);
+        let result = send_instruction(
+            &mut svm,
+            system_instruction::create_account(
+                &payer.pubkey(),
+                &counter,
+                lamports,
+                space as u64,
+                &program_id
+            ),
+            &payer,
+            &[&counter_keypair]
         );
-        let result = svm.send_transaction(transaction);
+        assert!(result.is_ok(), "Create account transaction should succeed");
+
+        let initialize = instruction::initialize_data(&program_id, &counter, &payer.pubkey(), 42);
+        let result = send_instruction(&mut svm, initialize.clone(), &payer, &[&counter_keypair]);
+        assert!(result.is_ok(), "Initialize data transaction should succeed");
+
+        let counter_data = get_counter(&svm, &counter);
+        assert_eq!(counter_data.count, 42);
+        assert_eq!(counter_data.authority, payer.pubkey());
+        assert_eq!(counter_data.version, CURRENT_VERSION);
+
+        let result = send_instruction(&mut svm, initialize, &payer, &[&counter_keypair]);
+        assert_instruction_error(result, InstructionError::Custom(CounterError::AlreadyInitialized as u32));
+    }
+
+    #[test]
+    fn test_increment_uninitialized_account_fails() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let space = CounterAccount::SIZE;
+        let lamports = svm.minimum_balance_for_rent_exemption(space);
+        let result = send_instruction(
+            &mut svm,
+            system_instruction::create_account(
+                &payer.pubkey(),
+                &counter,
+                lamports,
+                space as u64,
+                &program_id
+            ),
+            &payer,
+            &[&counter_keypair]
+        );
+        assert!(result.is_ok(), "Create account transaction should succeed");
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &CounterInstruction::IncrementCounter
+        );
+        assert_instruction_error(
+            result,
+            InstructionError::Custom(CounterError::UninitializedAccount as u32)
+        );
+        assert!(svm.get_account(&counter).unwrap().data.iter().all(|&byte| byte == 0));
+    }
+
+    #[test]
+    fn test_sum_counters() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypairs = [Keypair::new(), Keypair::new(), Keypair::new()];
+        let counters: Vec<Pubkey> = counter_keypairs.iter().map(|keypair| keypair.pubkey()).collect();
+
+        for (keypair, initial_value) in counter_keypairs.iter().zip([1, 10, 100]) {
+            let result = initialize_counter(&mut svm, program_id, &payer, keypair, initial_value);
+            assert!(result.is_ok(), "Initialize transaction should succeed");
+        }
+
+        let instruction = instruction::sum_counters(&program_id, &counters);
+        assert!(instruction.accounts.iter().all(|meta| !meta.is_writable));
+
+        let result = send_instruction(&mut svm, instruction, &payer, &[]);
+        let meta = result.expect("Sum counters should succeed");
+        assert_eq!(meta.return_data.data, 111u64.to_le_bytes());
+        assert_eq!(get_counter(&svm, &counters[0]).update_count, 0);
+
+        let foreign = Pubkey::new_unique();
+        svm.airdrop(&foreign, 1_000_000).expect("Failed to airdrop");
+        let result = send_instruction(
+            &mut svm,
+            instruction::sum_counters(&program_id, &[counters[0], foreign]),
+            &payer,
+            &[]
+        );
+        assert_instruction_error(result, InstructionError::IncorrectProgramId);
+    }
+
+    #[test]
+    fn test_public_counter_is_mutable_by_anyone() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+        let stranger = Keypair::new();
+
+        let result = send_initialize_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter_keypair,
+            &(CounterInstruction::InitializeCounterWithAccess {
+                initial_value: 42,
+                require_authority: false,
+            })
+        );
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+        assert!(!get_counter(&svm, &counter).require_authority);
+
+        let result = send_counter_instruction_with_authority(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &stranger,
+            &CounterInstruction::IncrementCounter
+        );
+        assert!(result.is_ok(), "Anyone should be able to increment a public counter");
+        assert_eq!(get_counter(&svm, &counter).count, 43);
+
+        let result = send_counter_instruction_with_authority(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &stranger,
+            &CounterInstruction::FreezeCounter
+        );
+        assert_instruction_error(result, InstructionError::Custom(CounterError::Unauthorized as u32));
+        assert!(!get_counter(&svm, &counter).is_frozen);
+    }
+
+    #[test]
+    fn test_public_counter_settings_need_authority() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+        let stranger = Keypair::new();
+
+        let result = send_initialize_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter_keypair,
+            &(CounterInstruction::InitializeCounterWithAccess {
+                initial_value: 42,
+                require_authority: false,
+            })
+        );
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        for setting in [
+            CounterInstruction::SetStep { step: 5 },
+            CounterInstruction::SetWrapping { wrapping: true },
+            CounterInstruction::SetModulus { modulus: 10 },
+        ] {
+            let result = send_counter_instruction_with_authority(
+                &mut svm,
+                program_id,
+                &payer,
+                &counter,
+                &stranger,
+                &setting
+            );
+            assert_instruction_error(
+                result,
+                InstructionError::Custom(CounterError::Unauthorized as u32)
+            );
+        }
+        let counter_data = get_counter(&svm, &counter);
+        assert_eq!(counter_data.step, 1);
+        assert!(!counter_data.wrapping);
+        assert_eq!(counter_data.modulus, 0);
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &(CounterInstruction::SetStep { step: 5 })
+        );
+        assert!(result.is_ok(), "Authority set step should succeed");
+        assert_eq!(get_counter(&svm, &counter).step, 5);
+    }
+
+    #[test]
+    fn test_lifetime_total_survives_reset() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 5);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+        assert_eq!(get_counter(&svm, &counter).lifetime_total, 0);
+
+        let result = send_increment(&mut svm, program_id, &counter, &payer);
         assert!(result.is_ok(), "Increment transaction should succeed");
+        assert_eq!(get_counter(&svm, &counter).lifetime_total, 1);
 
-        let logs = result.unwrap().logs;
-        println!("Transaction logs:\n{:#?}", logs);
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &CounterInstruction::ResetCounter
+        );
+        assert!(result.is_ok(), "Reset transaction should succeed");
+        assert_eq!(get_counter(&svm, &counter).lifetime_total, 1);
+
+        let result = send_increment(&mut svm, program_id, &counter, &payer);
+        assert!(result.is_ok(), "Increment transaction should succeed");
+        let counter_data = get_counter(&svm, &counter);
+        assert_eq!(counter_data.count, 1);
+        assert_eq!(counter_data.lifetime_total, 2);
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &(CounterInstruction::SetCounter { value: 100 })
+        );
+        assert!(result.is_ok(), "Set transaction should succeed");
+        assert_eq!(get_counter(&svm, &counter).lifetime_total, 2);
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &(CounterInstruction::Reinitialize { initial_value: 7 })
+        );
+        assert!(result.is_ok(), "Reinitialize transaction should succeed");
+        let counter_data = get_counter(&svm, &counter);
+        assert_eq!(counter_data.count, 7);
+        assert_eq!(counter_data.lifetime_total, 2);
+    }
+
+    #[test]
+    fn test_lifetime_total_counts_wrapped_increments() {
+        let (mut svm, payer, program_id) = setup();
+        let cycling_keypair = Keypair::new();
+        let wrapping_keypair = Keypair::new();
+        let cycling = cycling_keypair.pubkey();
+        let wrapping = wrapping_keypair.pubkey();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &cycling_keypair, 1);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &cycling,
+            &(CounterInstruction::SetModulus { modulus: 3 })
+        );
+        assert!(result.is_ok(), "Set modulus transaction should succeed");
+
+        for (count, lifetime_total) in [(2, 1), (0, 2), (1, 3)] {
+            let result = send_increment(&mut svm, program_id, &cycling, &payer);
+            assert!(result.is_ok(), "Increment transaction should succeed");
+            let counter_data = get_counter(&svm, &cycling);
+            assert_eq!(counter_data.count, count);
+            assert_eq!(counter_data.lifetime_total, lifetime_total);
+        }
+
+        let result = send_instruction(
+            &mut svm,
+            instruction::initialize_counter_with_overflow(
+                &program_id,
+                &wrapping,
+                &payer.pubkey(),
+                u64::MAX - 1,
+                OverflowPolicy::Wrap
+            ),
+            &payer,
+            &[&wrapping_keypair]
+        );
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &wrapping,
+            &(CounterInstruction::IncrementManyTimes { times: 3 })
+        );
+        assert!(result.is_ok(), "Wrapping increment should succeed");
+        let counter_data = get_counter(&svm, &wrapping);
+        assert_eq!(counter_data.count, 1);
+        assert_eq!(counter_data.lifetime_total, 3);
+    }
+
+    #[test]
+    fn test_last_mutator() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+        let stranger = Keypair::new();
+
+        let result = send_initialize_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter_keypair,
+            &(CounterInstruction::InitializeCounterWithAccess {
+                initial_value: 42,
+                require_authority: false,
+            })
+        );
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+        assert_eq!(get_counter(&svm, &counter).last_mutator, Pubkey::default());
+
+        let result = send_increment(&mut svm, program_id, &counter, &payer);
+        assert!(result.is_ok(), "Increment by the payer should succeed");
+        assert_eq!(get_counter(&svm, &counter).last_mutator, payer.pubkey());
+
+        let result = send_counter_instruction_with_authority(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &stranger,
+            &CounterInstruction::IncrementCounter
+        );
+        assert!(result.is_ok(), "Increment by a stranger should succeed");
+        let counter_data = get_counter(&svm, &counter);
+        assert_eq!(counter_data.count, 44);
+        assert_eq!(counter_data.last_mutator, stranger.pubkey());
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &(CounterInstruction::Reinitialize { initial_value: 0 })
+        );
+        assert!(result.is_ok(), "Reinitialize by the authority should succeed");
+        assert_eq!(get_counter(&svm, &counter).last_mutator, payer.pubkey());
+    }
+
+    #[test]
+    fn test_renounce_authority() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+        let stranger = Keypair::new();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 42);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let result = send_counter_instruction_with_authority(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &stranger,
+            &CounterInstruction::RenounceAuthority
+        );
+        assert_instruction_error(result, InstructionError::Custom(CounterError::Unauthorized as u32));
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &CounterInstruction::RenounceAuthority
+        );
+        assert!(result.is_ok(), "Renounce authority transaction should succeed");
+        let counter_data = get_counter(&svm, &counter);
+        assert_eq!(counter_data.authority, Pubkey::default());
+        assert!(!counter_data.require_authority);
+
+        let result = send_counter_instruction_with_authority(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &stranger,
+            &CounterInstruction::IncrementCounter
+        );
+        assert!(result.is_ok(), "Anyone should be able to increment after renouncing");
+        assert_eq!(get_counter(&svm, &counter).count, 43);
+    }
+
+    #[test]
+    fn test_private_counter_requires_authority() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+        let stranger = Keypair::new();
+
+        let result = send_initialize_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter_keypair,
+            &(CounterInstruction::InitializeCounterWithAccess {
+                initial_value: 42,
+                require_authority: true,
+            })
+        );
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let result = send_counter_instruction_with_authority(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &stranger,
+            &CounterInstruction::IncrementCounter
+        );
+        assert_instruction_error(result, InstructionError::Custom(CounterError::Unauthorized as u32));
+        assert_eq!(get_counter(&svm, &counter).count, 42);
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &CounterInstruction::IncrementCounter
+        );
+        assert!(result.is_ok(), "Authority increment should succeed");
+        assert_eq!(get_counter(&svm, &counter).count, 43);
+    }
+
+    #[test]
+    fn test_simulate_initialize_reports_rent() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let result = send_initialize_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter_keypair,
+            &(CounterInstruction::SimulateInitialize { initial_value: 42 })
+        );
+        let meta = result.expect("Simulate initialize should succeed");
+        let rent = svm.minimum_balance_for_rent_exemption(CounterAccount::SIZE);
+        assert_eq!(meta.return_data.data, rent.to_le_bytes());
+        // The signing counter may be recorded as an empty account, but nothing is allocated.
+        assert_eq!(svm.get_balance(&counter).unwrap_or(0), 0);
+        assert!(svm.get_account(&counter).is_none_or(|account| account.data.is_empty()));
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 42);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let result = send_initialize_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter_keypair,
+            &(CounterInstruction::SimulateInitialize { initial_value: 42 })
+        );
+        assert_instruction_error(
+            result,
+            InstructionError::Custom(CounterError::AlreadyInitialized as u32)
+        );
+    }
+
+    #[test]
+    fn test_initialize_if_needed() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let result = send_initialize_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter_keypair,
+            &(CounterInstruction::InitializeIfNeeded { initial_value: 42 })
+        );
+        assert!(result.is_ok(), "First initialize if needed should create the counter");
+        assert_eq!(get_counter(&svm, &counter).count, 42);
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &CounterInstruction::IncrementCounter
+        );
+        assert!(result.is_ok(), "Increment transaction should succeed");
+
+        let result = send_initialize_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter_keypair,
+            &(CounterInstruction::InitializeIfNeeded { initial_value: 7 })
+        );
+        assert!(result.is_ok(), "Initialize if needed on an existing counter should succeed");
+        let counter_data = get_counter(&svm, &counter);
+        assert_eq!(counter_data.count, 43);
+        assert_eq!(counter_data.update_count, 1);
+    }
+
+    #[test]
+    fn test_step_up_and_down() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 1);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        for (up, expected) in [(true, 2), (false, 1), (false, 0)] {
+            let result = send_counter_instruction(
+                &mut svm,
+                program_id,
+                &payer,
+                &counter,
+                &(CounterInstruction::Step { up })
+            );
+            assert!(result.is_ok(), "Step transaction should succeed");
+            assert_eq!(get_counter(&svm, &counter).count, expected);
+        }
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &(CounterInstruction::Step { up: false })
+        );
+        assert_instruction_error(result, InstructionError::Custom(CounterError::Underflow as u32));
+        assert_eq!(get_counter(&svm, &counter).count, 0);
+    }
+
+    #[test]
+    fn test_step_up_at_max_fails() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, u64::MAX);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &(CounterInstruction::Step { up: true })
+        );
+        assert_instruction_error(result, InstructionError::Custom(CounterError::Overflow as u32));
+        assert_eq!(get_counter(&svm, &counter).count, u64::MAX);
+    }
+
+    #[test]
+    fn test_freeze_counter() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, 42);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &CounterInstruction::FreezeCounter
+        );
+        assert!(result.is_ok(), "Freeze transaction should succeed");
+        assert!(get_counter(&svm, &counter).is_frozen);
+
+        // Covers the admin paths too: nothing, including resume or reinitialize, thaws it.
+        for instruction in [
+            CounterInstruction::IncrementCounter,
+            CounterInstruction::ResumeCounter,
+            CounterInstruction::FreezeCounter,
+            CounterInstruction::Reinitialize { initial_value: 0 },
+        ] {
+            let result = send_counter_instruction(&mut svm, program_id, &payer, &counter, &instruction);
+            assert_instruction_error(
+                result,
+                InstructionError::Custom(CounterError::CounterFrozen as u32)
+            );
+        }
+
+        let counter_data = get_counter(&svm, &counter);
+        assert_eq!(counter_data.count, 42);
+        assert!(counter_data.is_frozen);
+    }
+
+    #[test]
+    fn test_add_checked_saturates() {
+        let (mut svm, payer, program_id) = setup();
+        let counter_keypair = Keypair::new();
+        let counter = counter_keypair.pubkey();
+
+        let result = initialize_counter(&mut svm, program_id, &payer, &counter_keypair, u64::MAX - 5);
+        assert!(result.is_ok(), "Initialize transaction should succeed");
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &(CounterInstruction::AddChecked { amount: 10, saturate: false })
+        );
+        assert_instruction_error(
+            result,
+            InstructionError::Custom(CounterError::AddOverflow as u32)
+        );
+        assert_eq!(get_counter(&svm, &counter).count, u64::MAX - 5);
+
+        let result = send_counter_instruction(
+            &mut svm,
+            program_id,
+            &payer,
+            &counter,
+            &(CounterInstruction::AddChecked { amount: 10, saturate: true })
+        );
+        assert!(result.is_ok(), "Saturating add should succeed");
+        assert_eq!(get_counter(&svm, &counter).count, u64::MAX);
+    }
+
+    /// Supplies the sysvars the handlers read so `process_instruction` can run natively,
+    /// without loading the compiled program.
+    struct NativeSyscallStubs;
+
+    impl program_stubs::SyscallStubs for NativeSyscallStubs {
+        fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+            unsafe {
+                *(var_addr as *mut Clock) = Clock::default();
+            }
+            SUCCESS
+        }
+
+        fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+            unsafe {
+                *(var_addr as *mut Rent) = Rent::default();
+            }
+            SUCCESS
+        }
+
+        /// Dispatches straight into `process_instruction`. PDA signatures are honoured for
+        /// seeds of `WRAPPER_PROGRAM_ID`, the only caller in these tests.
+        fn sol_invoke_signed(
+            &self,
+            instruction: &Instruction,
+            account_infos: &[AccountInfo],
+            signers_seeds: &[&[&[u8]]]
+        ) -> ProgramResult {
+            let pda_signers: Vec<Pubkey> = signers_seeds
+                .iter()
+                .filter_map(|seeds| Pubkey::create_program_address(seeds, &WRAPPER_PROGRAM_ID).ok())
+                .collect();
+            let accounts = instruction.accounts
+                .iter()
+                .map(|meta| {
+                    let mut account = account_infos
+                        .iter()
+                        .find(|account| account.key == &meta.pubkey)
+                        .ok_or(ProgramError::NotEnoughAccountKeys)?
+                        .clone();
+                    account.is_signer |= pda_signers.contains(account.key);
+                    if meta.is_signer && !account.is_signer {
+                        return Err(ProgramError::MissingRequiredSignature);
+                    }
+                    account.is_writable &= meta.is_writable;
+                    Ok(account)
+                })
+                .collect::<Result<Vec<_>, ProgramError>>()?;
+            process_instruction(&instruction.program_id, &accounts, &instruction.data)
+        }
+    }
+
+    fn install_native_stubs() {
+        static INSTALL: std::sync::Once = std::sync::Once::new();
+        INSTALL.call_once(|| {
+            program_stubs::set_syscall_stubs(Box::new(NativeSyscallStubs));
+        });
+    }
+
+    /// Runs `run` natively against a program-owned counter holding `count` and controlled by
+    /// `authority_key`, returning the result and the counter's data afterwards.
+    fn run_native<F>(
+        count: u64,
+        authority_key: Pubkey,
+        authority_is_signer: bool,
+        run: F
+    ) -> (ProgramResult, Vec<u8>)
+        where F: FnOnce(&Pubkey, &[AccountInfo]) -> ProgramResult
+    {
+        install_native_stubs();
+        let program_id = Pubkey::new_unique();
+        let counter_key = Pubkey::new_unique();
+        let system_program_id = system_program::id();
+
+        let mut counter_lamports = counter_rent(&Rent::default());
+        let mut counter_data = borsh
+            ::to_vec(
+                &(CounterAccount {
+                    authority: authority_key,
+                    creator: authority_key,
+                    ..CounterAccount::new(count)
+                })
+            )
+            .unwrap();
+        let mut authority_lamports = 0;
+        let mut authority_data = [];
+        let accounts = [
+            AccountInfo::new(
+                &counter_key,
+                false,
+                true,
+                &mut counter_lamports,
+                &mut counter_data,
+                &program_id,
+                false,
+                0
+            ),
+            AccountInfo::new(
+                &authority_key,
+                authority_is_signer,
+                false,
+                &mut authority_lamports,
+                &mut authority_data,
+                &system_program_id,
+                false,
+                0
+            ),
+        ];
+
+        let result = run(&program_id, &accounts);
+        let data = accounts[0].data.borrow().to_vec();
+        (result, data)
+    }
+
+    /// Runs `instruction` through `process_instruction`; see `run_native`.
+    fn process_native(
+        instruction: &CounterInstruction,
+        count: u64,
+        authority_is_signer: bool
+    ) -> (ProgramResult, Vec<u8>) {
+        run_native(count, Pubkey::new_unique(), authority_is_signer, |program_id, accounts| {
+            process_instruction(program_id, accounts, &instruction.pack())
+        })
+    }
+
+    #[test]
+    fn test_native_increment() {
+        let (result, data) = process_native(&CounterInstruction::IncrementCounter, 41, true);
+        assert_eq!(result, Ok(()));
+
+        let counter_data = CounterAccount::unpack(&data).unwrap();
+        assert_eq!(counter_data.count, 42);
+        assert_eq!(counter_data.update_count, 1);
+    }
+
+    /// Program id of the stand-in caller in `test_native_cpi_increment`.
+    const WRAPPER_PROGRAM_ID: Pubkey = Pubkey::new_from_array([7; 32]);
+    const WRAPPER_AUTHORITY_SEED: &[u8] = b"authority";
+
+    /// Stand-in for another program: increments the counter as its PDA authority.
+    fn wrapper_program(counter_program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let (_, bump) = Pubkey::find_program_address(&[WRAPPER_AUTHORITY_SEED], &WRAPPER_PROGRAM_ID);
+        cpi::increment_counter_cpi(
+            counter_program_id,
+            &accounts[0],
+            &accounts[1],
+            &[&[WRAPPER_AUTHORITY_SEED, &[bump]]]
+        )
+    }
+
+    #[test]
+    fn test_native_cpi_increment() {
+        let (authority, _) = Pubkey::find_program_address(
+            &[WRAPPER_AUTHORITY_SEED],
+            &WRAPPER_PROGRAM_ID
+        );
+        let (result, data) = run_native(41, authority, false, wrapper_program);
+        assert_eq!(result, Ok(()));
+        assert_eq!(CounterAccount::unpack(&data).unwrap().count, 42);
+    }
+
+    #[test]
+    fn test_native_increment_requires_authority_signature() {
+        let (result, data) = process_native(&CounterInstruction::IncrementCounter, 41, false);
+        assert_eq!(result, Err(ProgramError::MissingRequiredSignature));
+        assert_eq!(CounterAccount::read_count(&data).unwrap(), 41);
     }
 }
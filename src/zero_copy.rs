@@ -0,0 +1,154 @@
+//! Read-only view of a `CounterAccount` cast straight from account data.
+//!
+//! Every field is stored as bytes, exactly as Borsh lays them out, so the struct has
+//! alignment 1 and can be cast from any slice without copying or deserializing. The Borsh
+//! `CounterAccount` stays the type handlers write and clients send.
+
+use borsh::BorshDeserialize;
+use bytemuck::{ Pod, Zeroable };
+use solana_program::{ program_error::ProgramError, pubkey::Pubkey };
+
+use crate::{
+    CounterAccount,
+    HISTORY_LEN,
+    MAX_NAME_LEN,
+    OverflowPolicy,
+};
+
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+pub struct CounterAccountZeroCopy {
+    version: u8,
+    count: [u8; 8],
+    authority: [u8; 32],
+    last_updated: [u8; 8],
+    update_count: [u8; 8],
+    step: [u8; 8],
+    is_paused: u8,
+    max_value: [u8; 8],
+    bump: u8,
+    min_value: [u8; 8],
+    creator: [u8; 32],
+    created_slot: [u8; 8],
+    history: [[u8; 8]; HISTORY_LEN],
+    head: u8,
+    name_len: u8,
+    name: [u8; MAX_NAME_LEN],
+    wrapping: u8,
+    is_frozen: u8,
+    modulus: [u8; 8],
+    require_authority: u8,
+    on_overflow: u8,
+    last_mutator: [u8; 32],
+    lifetime_total: [u8; 8],
+    pending_authority: [u8; 32],
+}
+
+// A field added to `CounterAccount` but not here would silently shift every later offset.
+const _: () = assert!(size_of::<CounterAccountZeroCopy>() == CounterAccount::SIZE);
+
+impl CounterAccountZeroCopy {
+    /// Views the first `CounterAccount::SIZE` bytes of `data`, rejecting the same buffers
+    /// with the same errors as `CounterAccount::unpack` via `CounterAccount::check_header`.
+    pub fn from_bytes(data: &[u8]) -> Result<&Self, ProgramError> {
+        Ok(bytemuck::from_bytes(CounterAccount::check_header(data)?))
+    }
+
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    pub fn count(&self) -> u64 {
+        u64::from_le_bytes(self.count)
+    }
+
+    pub fn authority(&self) -> Pubkey {
+        Pubkey::new_from_array(self.authority)
+    }
+
+    pub fn last_updated(&self) -> i64 {
+        i64::from_le_bytes(self.last_updated)
+    }
+
+    pub fn update_count(&self) -> u64 {
+        u64::from_le_bytes(self.update_count)
+    }
+
+    pub fn step(&self) -> u64 {
+        u64::from_le_bytes(self.step)
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.is_paused != 0
+    }
+
+    pub fn max_value(&self) -> u64 {
+        u64::from_le_bytes(self.max_value)
+    }
+
+    pub fn bump(&self) -> u8 {
+        self.bump
+    }
+
+    pub fn min_value(&self) -> u64 {
+        u64::from_le_bytes(self.min_value)
+    }
+
+    pub fn creator(&self) -> Pubkey {
+        Pubkey::new_from_array(self.creator)
+    }
+
+    pub fn created_slot(&self) -> u64 {
+        u64::from_le_bytes(self.created_slot)
+    }
+
+    /// Entry `index` of the history ring buffer, or `None` past `HISTORY_LEN`.
+    pub fn history(&self, index: usize) -> Option<u64> {
+        self.history.get(index).copied().map(u64::from_le_bytes)
+    }
+
+    pub fn head(&self) -> u8 {
+        self.head
+    }
+
+    /// The counter's name, empty if none was given or the stored bytes are invalid.
+    pub fn name(&self) -> &str {
+        self.name
+            .get(..self.name_len as usize)
+            .and_then(|name| core::str::from_utf8(name).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn wrapping(&self) -> bool {
+        self.wrapping != 0
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.is_frozen != 0
+    }
+
+    pub fn modulus(&self) -> u64 {
+        u64::from_le_bytes(self.modulus)
+    }
+
+    pub fn require_authority(&self) -> bool {
+        self.require_authority != 0
+    }
+
+    /// Fails if the stored byte isn't a known `OverflowPolicy`.
+    pub fn on_overflow(&self) -> Result<OverflowPolicy, ProgramError> {
+        Ok(OverflowPolicy::try_from_slice(&[self.on_overflow])?)
+    }
+
+    pub fn last_mutator(&self) -> Pubkey {
+        Pubkey::new_from_array(self.last_mutator)
+    }
+
+    pub fn lifetime_total(&self) -> u64 {
+        u64::from_le_bytes(self.lifetime_total)
+    }
+
+    pub fn pending_authority(&self) -> Pubkey {
+        Pubkey::new_from_array(self.pending_authority)
+    }
+}
@@ -0,0 +1,15 @@
+//! Compile check for the `no_std` build.
+//!
+//! The unit tests in `src/lib.rs` build the crate with `cfg(test)`, which links `std`, so on
+//! their own they would never notice `std` leaking into the handlers or types. Integration
+//! tests link the library as built for release, i.e. `no_std` with `alloc`, so `cargo test`
+//! fails to compile here as soon as the core needs `std`.
+
+use counter_program::{ COUNTER_ACCOUNT_SIZE, CounterAccount, CounterInstruction };
+
+#[test]
+fn test_no_std_build_links() {
+    let data = CounterInstruction::IncrementCounter.pack();
+    assert!(matches!(CounterInstruction::unpack(&data), Ok(CounterInstruction::IncrementCounter)));
+    assert_eq!(CounterAccount::SIZE, COUNTER_ACCOUNT_SIZE);
+}